@@ -0,0 +1,41 @@
+use std::path::PathBuf;
+use structopt::StructOpt;
+use sp_core::crypto::Ss58Codec;
+use sp_runtime::traits::BlakeTwo256;
+use node_template_runtime::{AccountId, Hash, pallet_quadratic_funding::merkle};
+
+/// Build the merkle root and the inclusion proofs of a voter allowlist.
+///
+/// The input is a JSON list of ss58 addresses, the root is used by `start_round`
+/// and each voter passes its proof to `vote` at the first time.
+#[derive(Debug, StructOpt)]
+pub struct AllowlistCmd {
+	/// Path of the JSON file with the list of addresses.
+	#[structopt(parse(from_os_str))]
+	pub input: PathBuf,
+}
+
+impl AllowlistCmd {
+	/// Print the root and proofs as JSON.
+	pub fn run(&self) -> sc_cli::Result<()> {
+		let file = std::fs::File::open(&self.input)?;
+		let addresses: Vec<String> = serde_json::from_reader(file)
+			.map_err(|e| format!("Invalid allowlist file: {}", e))?;
+		let accounts = addresses.iter()
+			.map(|address| AccountId::from_ss58check(address)
+				.map_err(|e| format!("Invalid address {}: {:?}", address, e)))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		let leaves: Vec<Hash> = accounts.iter().map(merkle::leaf::<BlakeTwo256, _>).collect();
+		let root = merkle::root::<BlakeTwo256>(leaves.clone());
+		let proofs: Vec<_> = addresses.iter().enumerate().map(|(index, address)| {
+			serde_json::json!({
+				"address": address,
+				"proof": merkle::proof::<BlakeTwo256>(leaves.clone(), index),
+			})
+		}).collect();
+
+		println!("{}", serde_json::json!({ "root": root, "proofs": proofs }));
+		Ok(())
+	}
+}
@@ -6,7 +6,7 @@ use jsonrpc_derive::rpc;
 use sp_runtime::{generic::BlockId, traits::{Block as BlockT, MaybeDisplay}};
 use sp_api::ProvideRuntimeApi;
 use sp_std::vec::Vec;
-pub use pallet_quadratic_funding_runtime_api::QuadraticFundingApi as QuadraticFundingRuntimeApi;
+pub use pallet_quadratic_funding_runtime_api::{QuadraticFundingApi as QuadraticFundingRuntimeApi, RankingProject, ParametersInfo, PoolMatching, AssetId, VoteImpact, RoundFee};
 pub use self::gen_client::Client as QuadraticFundingClient;
 
 
@@ -26,7 +26,77 @@ pub trait QuadraticFundingApi<AccountId, Hash> {
 	fn projects_per_round(
 		&self,
         round_id:u32,
-	) -> Result<Vec<(Hash, u32, u32, u32)>>;
+	) -> Result<Vec<RankingProject<Hash>>>;
+
+	/// The default parameters of a round and the round deposit in units of vote
+	#[rpc(name = "qf_parameters")]
+	fn parameters(&self) -> Result<ParametersInfo>;
+
+	/// The total tax and the tax paid to the fee destination of a round, by asset
+	#[rpc(name = "qf_roundFees")]
+	fn round_fees(
+		&self,
+        round_id:u32,
+	) -> Result<Vec<RoundFee>>;
+
+	/// The matching of a project from the general pool (`pool` is null) and each directed pool
+	#[rpc(name = "qf_projectMatching")]
+	fn project_matching(
+		&self,
+        round_id: u32,
+        hash: Hash,
+	) -> Result<Vec<PoolMatching>>;
+
+	/// (matching now, matching after) of a project if `who` contributes `amount` units of vote to it
+	#[rpc(name = "qf_estimateContribution")]
+	fn contribution_impact(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        hash: Hash,
+        amount: u32,
+	) -> Result<(u32, u32)>;
+
+	/// The total cost of several votes, as `qf_querVoteCost` of each (project, ballot)
+	#[rpc(name = "qf_querBatchVoteCost")]
+	fn batch_vote_cost(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        votes: Vec<(Hash, u32)>,
+	) -> Result<u32>;
+
+	/// The ballots per project that fit `budget` (in units of vote, or voice credits) split by `weights`, given the votes `who` already has
+	#[rpc(name = "qf_allocateBudget")]
+	fn allocate_budget(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        budget: u32,
+        weights: Vec<(Hash, u32)>,
+	) -> Result<Vec<(Hash, u32)>>;
+
+	/// The support area and matching of a project after a vote, and the change of matching of every project.
+	/// `eligible` is false if `who` can not cast the vote, `proof` is its allowlist proof as for a vote
+	#[rpc(name = "qf_estimateImpact")]
+	fn estimate_impact(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        hash: Hash,
+        ballot: i32,
+        proof: Option<Vec<Hash>>,
+	) -> Result<VoteImpact<Hash>>;
+
+	/// The cost of votes against a project, as `qf_querVoteCost`, 0 if the round does not accept them
+	#[rpc(name = "qf_querVoteAgainstCost")]
+	fn vote_against_cost(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        hash: Hash,
+        ballot: u32,
+	) -> Result<u32>;
 }
 
 /// A struct that implements the [`QuadraticFundingApi`].
@@ -82,21 +152,138 @@ where
 		let at = BlockId::hash(best);
 		api.vote_cost(&at, who, round_id, project_hash, ballot).map_err(|e| RpcError {
 			code: ErrorCode::ServerError(Error::RuntimeError.into()),
-			message: "Unable to query dispatch info.".into(),
+			message: "Unable to query vote cost.".into(),
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
 	fn projects_per_round(
 		&self,
         round_id:u32,
-	) -> Result<Vec<(Hash, u32, u32, u32)>> {
+	) -> Result<Vec<RankingProject<Hash>>> {
 		let api = self.client.runtime_api();
 		let best = self.client.info().best_hash;
 		let at = BlockId::hash(best);
 		api.projects_per_round(&at, round_id).map_err(|e| RpcError {
 			code: ErrorCode::ServerError(Error::RuntimeError.into()),
-			message: "Unable to query dispatch info.".into(),
+			message: "Unable to query projects of the round.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn parameters(&self) -> Result<ParametersInfo> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.parameters(&at).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query parameters.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn round_fees(
+		&self,
+        round_id:u32,
+	) -> Result<Vec<RoundFee>> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.round_fees(&at, round_id).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query round fees.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn project_matching(
+		&self,
+        round_id: u32,
+        hash: Hash,
+	) -> Result<Vec<PoolMatching>> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.project_matching(&at, round_id, hash).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query project matching.".into(),
 			data: Some(format!("{:?}", e).into()),
 		})
 	}
-}
\ No newline at end of file
+	fn contribution_impact(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        hash: Hash,
+        amount: u32,
+	) -> Result<(u32, u32)> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.contribution_impact(&at, who, round_id, hash, amount).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to estimate contribution.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn batch_vote_cost(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        votes: Vec<(Hash, u32)>,
+	) -> Result<u32> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.batch_vote_cost(&at, who, round_id, votes).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query batch vote cost.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn allocate_budget(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        budget: u32,
+        weights: Vec<(Hash, u32)>,
+	) -> Result<Vec<(Hash, u32)>> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.allocate_budget(&at, who, round_id, budget, weights).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to allocate budget.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn estimate_impact(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        hash: Hash,
+        ballot: i32,
+        proof: Option<Vec<Hash>>,
+	) -> Result<VoteImpact<Hash>> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.estimate_impact(&at, who, round_id, hash, ballot, proof).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to estimate vote impact.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+	fn vote_against_cost(
+		&self,
+        who: AccountId,
+        round_id: u32,
+        hash: Hash,
+        ballot: u32,
+	) -> Result<u32> {
+		let api = self.client.runtime_api();
+		let best = self.client.info().best_hash;
+		let at = BlockId::hash(best);
+		api.vote_against_cost(&at, who, round_id, hash, ballot).map_err(|e| RpcError {
+			code: ErrorCode::ServerError(Error::RuntimeError.into()),
+			message: "Unable to query vote against cost.".into(),
+			data: Some(format!("{:?}", e).into()),
+		})
+	}
+}
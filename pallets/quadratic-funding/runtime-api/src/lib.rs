@@ -5,13 +5,22 @@
 use codec::{self, Codec, Encode};
 use sp_runtime::traits::MaybeDisplay;
 use sp_std::vec::Vec;
+pub use pallet_quadratic_funding::{RankingProject, ParametersInfo, PoolMatching, AssetId, VoteImpact, RoundFee};
 
 sp_api::decl_runtime_apis! {
 	pub trait QuadraticFundingApi<AccountId, Hash> where
 	AccountId: Clone + MaybeDisplay + Encode,
-	Hash: Codec + MaybeDisplay
+	Hash: Codec + MaybeDisplay,
 	{
 		fn vote_cost(who: AccountId, round_id:u32, hash: Hash, ballot: u32) -> u32;
-		fn projects_per_round(round_id:u32) -> Vec<(Hash, u32, u32, u32)>;
+		fn projects_per_round(round_id:u32) -> Vec<RankingProject<Hash>>;
+		fn parameters() -> ParametersInfo;
+		fn round_fees(round_id: u32) -> Vec<RoundFee>;
+		fn project_matching(round_id: u32, hash: Hash) -> Vec<PoolMatching>;
+		fn contribution_impact(who: AccountId, round_id: u32, hash: Hash, amount: u32) -> (u32, u32);
+		fn batch_vote_cost(who: AccountId, round_id: u32, votes: Vec<(Hash, u32)>) -> u32;
+		fn allocate_budget(who: AccountId, round_id: u32, budget: u32, weights: Vec<(Hash, u32)>) -> Vec<(Hash, u32)>;
+		fn estimate_impact(who: AccountId, round_id: u32, hash: Hash, ballot: i32, proof: Option<Vec<Hash>>) -> VoteImpact<Hash>;
+		fn vote_against_cost(who: AccountId, round_id: u32, hash: Hash, ballot: u32) -> u32;
 	}
 }
\ No newline at end of file
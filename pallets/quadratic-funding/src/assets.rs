@@ -0,0 +1,58 @@
+//! Assets a round can vote and match in.
+//! The native currency is always available, other assets come from the runtime through `MultiAsset`,
+//! which mirrors the transfer part of `orml_traits::MultiCurrency` (the node runtime uses pallet-assets).
+
+use frame_support::{codec::{Encode, Decode}, dispatch::{DispatchResult, DispatchError}, traits::{Currency, ExistenceRequirement::KeepAlive}};
+use sp_runtime::{RuntimeDebug, traits::Zero};
+use sp_std::marker::PhantomData;
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+use crate::{Config, BalanceOf};
+
+/// The asset used to vote or to match in a round
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum AssetId {
+	/// The `Currency` of the pallet
+	Native,
+	/// An asset of the runtime, by its id
+	Token(u32),
+}
+
+impl Default for AssetId {
+	fn default() -> Self {
+		AssetId::Native
+	}
+}
+
+/// Move any asset between accounts
+pub trait MultiAsset<AccountId> {
+	type Balance;
+
+	/// The free balance of `who` in `asset`
+	fn free_balance(asset: AssetId, who: &AccountId) -> Self::Balance;
+
+	/// Transfer `amount` of `asset`, the sender must be kept alive
+	fn transfer(asset: AssetId, from: &AccountId, to: &AccountId, amount: Self::Balance) -> DispatchResult;
+}
+
+/// Only the native currency, every other asset is rejected
+pub struct NativeAsset<T>(PhantomData<T>);
+
+impl<T: Config> MultiAsset<T::AccountId> for NativeAsset<T> {
+	type Balance = BalanceOf<T>;
+
+	fn free_balance(asset: AssetId, who: &T::AccountId) -> BalanceOf<T> {
+		match asset {
+			AssetId::Native => T::Currency::free_balance(who),
+			AssetId::Token(_) => Zero::zero(),
+		}
+	}
+
+	fn transfer(asset: AssetId, from: &T::AccountId, to: &T::AccountId, amount: BalanceOf<T>) -> DispatchResult {
+		match asset {
+			AssetId::Native => T::Currency::transfer(from, to, amount, KeepAlive),
+			AssetId::Token(_) => Err(DispatchError::Other("Unsupported asset")),
+		}
+	}
+}
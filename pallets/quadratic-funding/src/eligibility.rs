@@ -0,0 +1,127 @@
+//! Policies deciding which accounts are allowed to vote.
+//! The runtime picks one of them (or a tuple of them) as `Config::VoterEligibility`,
+//! so that sybil accounts funded from a single wallet can not cheaply manufacture support area.
+
+use frame_support::{codec::{Encode, Decode}, traits::{Currency, Get}};
+use pallet_identity::Judgement;
+use sp_runtime::{RuntimeDebug, traits::Saturating};
+use sp_std::marker::PhantomData;
+use crate::{Config, Module, BalanceOf};
+
+/// Decide whether an account is allowed to vote.
+pub trait VoterEligibility<AccountId> {
+	/// Return `true` if `who` is allowed to vote.
+	fn is_eligible(who: &AccountId) -> bool;
+}
+
+/// Everyone is eligible, this is the behaviour before any policy is applied.
+impl<AccountId> VoterEligibility<AccountId> for () {
+	fn is_eligible(_who: &AccountId) -> bool {
+		true
+	}
+}
+
+/// Both policies must be satisfied.
+impl<AccountId, A, B> VoterEligibility<AccountId> for (A, B) where
+	A: VoterEligibility<AccountId>,
+	B: VoterEligibility<AccountId>,
+{
+	fn is_eligible(who: &AccountId) -> bool {
+		A::is_eligible(who) && B::is_eligible(who)
+	}
+}
+
+/// All three policies must be satisfied.
+impl<AccountId, A, B, C> VoterEligibility<AccountId> for (A, B, C) where
+	A: VoterEligibility<AccountId>,
+	B: VoterEligibility<AccountId>,
+	C: VoterEligibility<AccountId>,
+{
+	fn is_eligible(who: &AccountId) -> bool {
+		A::is_eligible(who) && B::is_eligible(who) && C::is_eligible(who)
+	}
+}
+
+/// The identity level of a voter, mirrors the judgements of pallet-identity
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum IdentityLevel {
+	/// No identity is registered
+	Anonymous,
+	/// An identity is registered without any judgement yet
+	Unknown,
+	Reasonable,
+	KnownGood,
+	OutOfDate,
+	LowQuality,
+	Erroneous,
+}
+
+/// Get the identity level of an account, used to weight votes in the support area
+pub trait IdentityLevelOf<AccountId> {
+	fn identity_level(who: &AccountId) -> IdentityLevel;
+}
+
+/// Every account is anonymous
+impl<AccountId> IdentityLevelOf<AccountId> for () {
+	fn identity_level(_who: &AccountId) -> IdentityLevel {
+		IdentityLevel::Anonymous
+	}
+}
+
+/// Require a `Reasonable` or `KnownGood` judgement from any registrar of pallet-identity.
+/// As `IdentityLevelOf`, the best positive judgement wins, otherwise the worst negative one.
+pub struct IdentityJudgement<T>(PhantomData<T>);
+
+impl<T: pallet_identity::Trait> VoterEligibility<T::AccountId> for IdentityJudgement<T> {
+	fn is_eligible(who: &T::AccountId) -> bool {
+		pallet_identity::Module::<T>::identity(who).map_or(false, |registration| {
+			registration.judgements.iter().any(|(_, judgement)| {
+				matches!(judgement, Judgement::Reasonable | Judgement::KnownGood)
+			})
+		})
+	}
+}
+
+impl<T: pallet_identity::Trait> IdentityLevelOf<T::AccountId> for IdentityJudgement<T> {
+	fn identity_level(who: &T::AccountId) -> IdentityLevel {
+		let registration = match pallet_identity::Module::<T>::identity(who) {
+			Some(registration) => registration,
+			None => return IdentityLevel::Anonymous,
+		};
+		let has = |level: &Judgement<_>| registration.judgements.iter().any(|(_, judgement)| judgement == level);
+		if has(&Judgement::KnownGood) {
+			IdentityLevel::KnownGood
+		} else if has(&Judgement::Reasonable) {
+			IdentityLevel::Reasonable
+		} else if has(&Judgement::Erroneous) {
+			IdentityLevel::Erroneous
+		} else if has(&Judgement::LowQuality) {
+			IdentityLevel::LowQuality
+		} else if has(&Judgement::OutOfDate) {
+			IdentityLevel::OutOfDate
+		} else {
+			IdentityLevel::Unknown
+		}
+	}
+}
+
+/// Require the free balance of the voter to be at least `Min`.
+pub struct MinimumBalance<T, Min>(PhantomData<(T, Min)>);
+
+impl<T: Config, Min: Get<BalanceOf<T>>> VoterEligibility<T::AccountId> for MinimumBalance<T, Min> {
+	fn is_eligible(who: &T::AccountId) -> bool {
+		T::Currency::free_balance(who) >= Min::get()
+	}
+}
+
+/// Require the account to be created at least `Age` blocks ago.
+/// The creation block is recorded by this pallet as `OnNewAccount`, accounts without a record are
+/// considered to be created at genesis.
+pub struct MinimumAccountAge<T, Age>(PhantomData<(T, Age)>);
+
+impl<T: Config, Age: Get<T::BlockNumber>> VoterEligibility<T::AccountId> for MinimumAccountAge<T, Age> {
+	fn is_eligible(who: &T::AccountId) -> bool {
+		let birth = <Module<T>>::account_birth(who).unwrap_or_default();
+		<frame_system::Module<T>>::block_number() >= birth.saturating_add(Age::get())
+	}
+}
@@ -6,13 +6,29 @@
 /// debug guide https://substrate.dev/recipes/runtime-printing.html
 
 use frame_support::{
-	decl_module, decl_storage, decl_event, decl_error, dispatch, debug, ensure,
-	traits::{Currency, EnsureOrigin, ReservableCurrency, OnUnbalanced, Get, ExistenceRequirement::{KeepAlive}},
+	decl_module, decl_storage, decl_event, decl_error, dispatch, debug, ensure, storage::with_transaction, transactional,
+	weights::Weight,
+	traits::{
+		Currency, EnsureOrigin, ReservableCurrency, LockableCurrency, LockIdentifier, OnUnbalanced, OnNewAccount,
+		OnKilledAccount, Get,
+		ExistenceRequirement::{KeepAlive}, WithdrawReasons, WithdrawReason, Imbalance,
+	},
 };
-use sp_runtime::{ModuleId, traits::{ Hash, AccountIdConversion}};
+use sp_runtime::{ModuleId, Percent, RuntimeDebug, TransactionOutcome, helpers_128bit::multiply_by_rational, traits::{ Hash, AccountIdConversion, IntegerSquareRoot}};
 use frame_support::codec::{Encode, Decode};
 use frame_system::{ensure_signed};
 use sp_std::{vec, vec::Vec, convert::{TryInto}};
+#[cfg(feature = "std")]
+use serde::{Serialize, Deserialize};
+
+pub mod eligibility;
+pub use eligibility::{
+	VoterEligibility, IdentityJudgement, MinimumBalance, MinimumAccountAge, IdentityLevel, IdentityLevelOf,
+};
+pub mod merkle;
+pub mod assets;
+pub mod migrations;
+pub use assets::{AssetId, MultiAsset, NativeAsset};
 
 #[cfg(test)]
 mod mock;
@@ -23,11 +39,153 @@ mod tests;
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
 pub struct Project<AccountId> {
 	pub total_votes: u128,
+	// sum of ballots multiplied by the identity weight of each voter
+	pub weighted_votes: u128,
 	pub grants: u128,
 	pub support_area: u128,
 	pub withdrew: u128,
 	pub name: Vec<u8>,
 	pub owner: AccountId,
+	// ballots of the team which are excluded from the support area
+	pub discounted_votes: u128,
+	// the track of the round this project competes in, `None` for the pool of the round
+	pub track: Option<u32>,
+	// ballots against the project, and their sum multiplied by the identity weight of each voter
+	pub votes_against: u128,
+	pub weighted_against: u128,
+	// the part of grants matched at settlement, in the matching asset, the rest is in the vote asset
+	pub matching: u128,
+}
+
+/// Ballots of an account for or against a project, with the identity weight fixed at the first vote
+#[derive(Encode, Decode, Default, Clone, PartialEq)]
+pub struct VoteRecord {
+	pub ballots: u128,
+	pub weight: u32,
+	pub against: u128,
+	// whether the votes are discounted self votes, fixed at the first vote as the weight
+	pub discounted: bool,
+}
+
+/// The layout of the storage of this pallet, see `migrations`
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum Releases {
+	/// Rounds with an explicit id and without parameters, votes as plain ballots
+	V1_0_0,
+	/// Rounds with parameters, votes recorded with the identity weight of the voter
+	V2_0_0,
+}
+
+impl Default for Releases {
+	fn default() -> Self {
+		Releases::V1_0_0
+	}
+}
+
+/// Parameters of a round, seeded from the Config defaults and overridable in `start_round`
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct RoundParams {
+	pub unit_of_vote: u128,
+	pub number_of_unit_per_vote: u128,
+	pub fee_ratio_per_vote: u128,
+	pub name_min_length: u32,
+	pub name_max_length: u32,
+	/// The asset paid for votes
+	pub vote_asset: AssetId,
+	/// The asset of donations and matching
+	pub matching_asset: AssetId,
+	/// Whether votes are paid, locked or counted in voice credits
+	pub vote_mode: VoteMode,
+	/// The voice credits granted to each voter at its first vote in `VoteMode::Credits`
+	pub credits_per_voter: u128,
+	/// Whether votes against projects are accepted and where their payment goes
+	pub negative_votes: NegativeVotes,
+}
+
+/// What a round does with votes against projects
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum NegativeVotes {
+	/// Only votes for projects are accepted
+	Disabled,
+	/// The payment of votes against projects goes to the pool of the round, minus the fee
+	ToPool,
+	/// The payment of votes against projects is burned, it must be in the native currency
+	Burn,
+}
+
+impl Default for NegativeVotes {
+	fn default() -> Self {
+		NegativeVotes::Disabled
+	}
+}
+
+/// How the cost of votes is charged in a round
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub enum VoteMode {
+	/// The cost is transferred to the project, minus the fee
+	Spend,
+	/// The cost is locked in the native currency until the round ends and each voter unlocks it, only sponsor funds are distributed
+	Lock,
+	/// The cost is paid in voice credits of the round, the pool is distributed by the share of weighted votes
+	Credits,
+	/// Voters `contribute` any amount instead of ballots, a contribution counts as its square root in units of vote
+	Contribution,
+}
+
+impl Default for VoteMode {
+	fn default() -> Self {
+		VoteMode::Spend
+	}
+}
+
+/// Pallet-wide defaults, the Config constants are only used as their genesis values
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct QfParameters<Balance> {
+	/// The default parameters of a new round
+	pub round: RoundParams,
+	/// The deposit reserved from an account which opens a round
+	pub round_deposit: Balance,
+	/// The bounds of the parameters of a round
+	pub bounds: ParamBounds,
+	/// The share of transaction fees the runtime credits to the designated round
+	pub fee_share: Percent,
+}
+
+/// Designed for rpc return, the default parameters of a round and the round deposit,
+/// narrowed from u128 as serde_json can not handle it, the round deposit is in UnitOfVote
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ParametersInfo {
+	pub unit_of_vote: u64,
+	pub number_of_unit_per_vote: u32,
+	pub fee_ratio_per_vote: u32,
+	pub name_min_length: u32,
+	pub name_max_length: u32,
+	pub vote_asset: AssetId,
+	pub matching_asset: AssetId,
+	pub vote_mode: VoteMode,
+	pub credits_per_voter: u32,
+	pub negative_votes: NegativeVotes,
+	pub round_deposit: u32,
+}
+
+/// The bounds checked by `validate_params` on the parameters of a round
+#[derive(Encode, Decode, Default, Clone, PartialEq, Eq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct ParamBounds {
+	pub min_unit_of_vote: u128,
+	pub max_unit_of_vote: u128,
+	pub min_number_of_unit_per_vote: u128,
+	pub max_number_of_unit_per_vote: u128,
+	/// The fee can not be lower than this, nor exceed `number_of_unit_per_vote`
+	pub min_fee_ratio_per_vote: u128,
+	pub max_credits_per_voter: u128,
+	/// The largest multiplier of an identity level in `set_identity_weights`
+	pub max_identity_weight: u32,
 }
 
 #[derive(Encode, Decode, Default, Clone, PartialEq)]
@@ -37,19 +195,162 @@ pub struct Round {
 	pub pre_tax_support_pool: u128,
 	pub total_support_area: u128,
 	pub total_tax: u128,
+	pub params: RoundParams,
+	// the part of total_tax moved to FeeDestination at settlement
+	pub tax_paid: u128,
+	// what is left in the pallet account for this round after settlement or cancellation
+	pub unallocated: u128,
+	// the part of total_tax collected from votes, in the vote asset, the rest is in the matching asset
+	pub vote_tax: u128,
+	// like unallocated, for the vote asset when it differs from the matching asset
+	pub unallocated_votes: u128,
+	// the payment of votes against projects added to support_pool, minus the fee
+	pub votes_to_pool: u128,
+	// what the voters of a cancelled round can still claim back, in the vote asset
+	pub voter_refunds: u128,
+	// the number of projects registered in the round, at most MAX_PROJECTS
+	pub project_count: u32,
+	// the part of tax_paid in the vote asset
+	pub vote_tax_paid: u128,
+}
+
+/// How votes from the owner or members of a project are treated in a round
+#[derive(Encode, Decode, Clone, Copy, PartialEq, Eq, RuntimeDebug)]
+pub enum SelfVotePolicy {
+	/// Self votes count like any other vote
+	Allow,
+	/// Self votes are rejected
+	Forbid,
+	/// Self votes are accepted, but excluded from the support area
+	Discount,
+}
+
+impl Default for SelfVotePolicy {
+	fn default() -> Self {
+		SelfVotePolicy::Allow
+	}
+}
+
+/// Designed for rpc return, amounts are in UnitOfVote as serde_json can not handle u128
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct RankingProject<ProjectHash> {
+	pub project_id: ProjectHash,
+	pub total_votes: u32,
+	pub grants: u32,
+	pub support_grants: u32,
+	pub discounted_votes: u32,
+	pub votes_against: u32,
+	pub track: Option<u32>,
+	// the asset of grants
+	pub vote_asset: AssetId,
+	// the asset of support_grants
+	pub matching_asset: AssetId,
+}
+
+/// Designed for rpc return, the effect of a vote simulated by `estimate_impact`, matching is in UnitOfVote
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct VoteImpact<ProjectHash> {
+	// whether `who` can cast the vote, every other field is empty if not
+	pub eligible: bool,
+	// the support area of the voted project after the vote, reduced by the votes against it
+	pub support_area: u64,
+	// the matching of the voted project after the vote
+	pub matching: u32,
+	// the change of matching of every project of the round, the voted one included
+	pub changes: Vec<(ProjectHash, i64)>,
+}
+
+/// What a directed donation can be restricted to
+#[derive(Encode, Decode, Clone, PartialEq, Eq, RuntimeDebug)]
+pub enum DirectedTarget<Hash> {
+	/// A single project
+	Project(Hash),
+	/// Every project with this tag
+	Tag(Vec<u8>),
+	/// Every project of a track
+	Track(u32),
+}
+
+/// A matching sub-pool of a round, funded by donations with the same targets
+#[derive(Encode, Decode, Clone, PartialEq, RuntimeDebug)]
+pub struct DirectedPool<Hash> {
+	pub targets: Vec<DirectedTarget<Hash>>,
+	// after tax
+	pub amount: u128,
+}
+
+impl<Hash: PartialEq> DirectedPool<Hash> {
+	/// Whether a project with `hash`, `track` and `tags` can be matched from this pool
+	pub fn targets_project(&self, hash: &Hash, track: Option<u32>, tags: &[Vec<u8>]) -> bool {
+		self.targets.iter().any(|target| match target {
+			DirectedTarget::Project(h) => h == hash,
+			DirectedTarget::Tag(tag) => tags.contains(tag),
+			DirectedTarget::Track(t) => track == Some(*t),
+		})
+	}
+}
+
+/// A category of projects inside a round, matched from its own pool
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+pub struct Track {
+	pub name: Vec<u8>,
+	pub support_pool: u128,
+	pub pre_tax_support_pool: u128,
+	pub total_support_area: u128,
+}
+
+/// Designed for rpc return, the tax of a round collected in an asset, in UnitOfVote
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct RoundFee {
+	pub asset: AssetId,
+	pub total_tax: u32,
+	// the part of total_tax moved to FeeDestination at settlement
+	pub tax_paid: u32,
 }
 
-// TODO: This is designed for rpc return, need to investigate feasibility, use tuple instead
-// #[derive(Encode, Decode, Default, Clone, PartialEq)]
-// pub struct RankingProject<ProjectHash> {
-// 	pub project_id: ProjectHash,
-// 	pub total_votes: u32,
-// 	pub grants: u32,
-// 	pub support_grants: u32,
-// }
+/// Designed for rpc return, the matching of a project from its main pool (`None`, the pool of its track or of the round)
+/// or a directed pool
+#[derive(Encode, Decode, Default, Clone, PartialEq, RuntimeDebug)]
+#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
+pub struct PoolMatching {
+	pub pool: Option<u32>,
+	pub matching: u32,
+	pub asset: AssetId,
+}
+
+/// The maximum number of targets of a directed donation
+pub const MAX_TARGETS: usize = 16;
+/// The maximum number of directed pools in a round
+pub const MAX_DIRECTED_POOLS: usize = 32;
+/// The maximum number of tags of a project
+pub const MAX_TAGS: usize = 8;
+/// The maximum number of tracks in a round
+pub const MAX_TRACKS: usize = 16;
+/// The maximum number of projects voted in one `vote_many`
+pub const MAX_BATCH_VOTES: usize = 16;
+/// The maximum number of projects in a round, which are all settled by `end_round`
+pub const MAX_PROJECTS: u32 = 128;
+/// The maximum number of managers of a round, the operator included
+pub const MAX_ROUND_MANAGERS: usize = 16;
+
+/// What a vote costs and the vote record it starts from, checked before anything is paid
+struct VoteTerms<Hash> {
+	hash: Hash,
+	vote_hash: Hash,
+	record: VoteRecord,
+	voted: u128,
+	ballot: u128,
+	cost: u128,
+	amount: u128,
+	fee: u128,
+}
 
 type ProjectOf<T> = Project<<T as frame_system::Trait>::AccountId>;
 type BalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::Balance;
+type ParametersOf<T> = QfParameters<BalanceOf<T>>;
 type NegativeImbalanceOf<T> = <<T as Config>::Currency as Currency<<T as frame_system::Trait>::AccountId>>::NegativeImbalance;
 
 /// Configure the pallet by specifying the parameters and types on which it depends.
@@ -65,14 +366,24 @@ pub trait Config: frame_system::Trait {
     type Event: From<Event<Self>> + Into<<Self as frame_system::Trait>::Event>;
 
 	/// The currency trait.
-	type Currency: ReservableCurrency<Self::AccountId>;
+	type Currency: ReservableCurrency<Self::AccountId> + LockableCurrency<Self::AccountId, Moment = Self::BlockNumber>;
 
-	/// UnitOfVote, 0.001 Unit token
+	/// The lock of votes in rounds with `VoteMode::Lock`
+	type VoteLockId: Get<LockIdentifier>;
+
+	/// UnitOfVote, 0.001 Unit token, genesis value of the default round parameters
 	type UnitOfVote: Get<u128>;
 
 	/// What to do with slashed funds.
 	type Slashed: OnUnbalanced<NegativeImbalanceOf<Self>>;
 
+	/// Where the tax of a round goes at settlement.
+	type FeeDestination: OnUnbalanced<NegativeImbalanceOf<Self>>;
+
+	/// Move the vote and matching assets of rounds, `NativeAsset` only supports `Currency`.
+	/// The tax in other assets goes to `TreasuryAccount`.
+	type Assets: MultiAsset<Self::AccountId, Balance = BalanceOf<Self>>;
+
 	/// Number of base unit for each vote
 	type NumberOfUnitPerVote: Get<u128>;
 
@@ -85,7 +396,23 @@ pub trait Config: frame_system::Trait {
 	/// The maximum length of project name
 	type NameMaxLength: Get<usize>;
 
-	
+	/// Who is allowed to vote, see `eligibility` for the available policies
+	type VoterEligibility: VoterEligibility<Self::AccountId>;
+
+	/// The identity level of voters, used to weight their votes
+	type IdentityLevels: IdentityLevelOf<Self::AccountId>;
+
+	/// The deposit reserved from an account which opens a round, genesis value of `GlobalParameters`
+	type RoundDeposit: Get<BalanceOf<Self>>;
+
+	/// Origin who can spend treasury funds on the pool of a round
+	type TreasuryOrigin: EnsureOrigin<Self::Origin>;
+
+	/// The account of the treasury
+	type TreasuryAccount: Get<Self::AccountId>;
+
+	/// The share of transaction fees credited to the designated round, genesis value of `GlobalParameters`
+	type FeeShare: Get<Percent>;
 }
 
 // The pallet's runtime storage items.
@@ -99,7 +426,75 @@ decl_storage! {
 		// Map, each round start with an id => bool 
 		Rounds get(fn rounds): map hasher(blake2_128_concat) u32 => Round;
 		Projects get(fn projects): double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::Hash => ProjectOf<T>;
-		ProjectVotes: double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId => u128;
+		ProjectVotes get(fn project_votes): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId => VoteRecord;
+		// The block in which an account was created, used by the account age eligibility policy
+		AccountBirth get(fn account_birth): map hasher(blake2_128_concat) T::AccountId => Option<T::BlockNumber>;
+		// Merkle root of the accounts allowed to vote in a round, any account can vote if there is none
+		Allowlists get(fn allowlist): map hasher(blake2_128_concat) u32 => Option<T::Hash>;
+		// Accounts which have proved their inclusion in the allowlist of a round
+		VerifiedVoters get(fn verified_voters): double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::AccountId => bool;
+		// Multiplier of each identity level in a round, levels not in the table count as 1
+		IdentityWeights get(fn identity_weights): map hasher(blake2_128_concat) u32 => Vec<(IdentityLevel, u32)>;
+		SelfVotePolicies get(fn self_vote_policy): map hasher(blake2_128_concat) u32 => SelfVotePolicy;
+		// Team members of a project besides its owner, using vote_hash as the first key
+		ProjectMembers get(fn project_members): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId => bool;
+		// Accounts which can control a round besides AdminOrigin
+		RoundManagers get(fn round_managers): map hasher(blake2_128_concat) u32 => Vec<T::AccountId>;
+		// The id of the next round to be started
+		NextRoundId get(fn next_round_id): u32;
+		// The account which opened a round and its reserved deposit, rounds started by admin have no operator
+		RoundOperators get(fn round_operator): map hasher(blake2_128_concat) u32 => Option<(T::AccountId, BalanceOf<T>)>;
+		// Pallet-wide defaults, set by `set_parameters`
+		GlobalParameters get(fn parameters): ParametersOf<T> = QfParameters {
+			round: RoundParams {
+				unit_of_vote: T::UnitOfVote::get(),
+				number_of_unit_per_vote: T::NumberOfUnitPerVote::get(),
+				fee_ratio_per_vote: T::FeeRatioPerVote::get(),
+				name_min_length: T::NameMinLength::get() as u32,
+				name_max_length: T::NameMaxLength::get() as u32,
+				vote_asset: AssetId::Native,
+				matching_asset: AssetId::Native,
+				vote_mode: VoteMode::Spend,
+				credits_per_voter: 0,
+				negative_votes: NegativeVotes::Disabled,
+			},
+			round_deposit: T::RoundDeposit::get(),
+			// within three orders of magnitude of the defaults, voice credits must fit in the rpc values
+			bounds: ParamBounds {
+				min_unit_of_vote: 1,
+				max_unit_of_vote: T::UnitOfVote::get().saturating_mul(1_000),
+				min_number_of_unit_per_vote: 1,
+				max_number_of_unit_per_vote: T::NumberOfUnitPerVote::get().saturating_mul(1_000),
+				min_fee_ratio_per_vote: 1,
+				max_credits_per_voter: u32::max_value() as u128,
+				max_identity_weight: 10,
+			},
+			fee_share: T::FeeShare::get(),
+		};
+		// The round credited by the `OnUnbalanced` implementation of this pallet
+		DesignatedRound get(fn designated_round): Option<u32>;
+		// The round which receives the unallocated funds of a round at settlement
+		Successors get(fn successor): map hasher(blake2_128_concat) u32 => Option<u32>;
+		// The amount donated by each sponsor of a round, before tax
+		Sponsors get(fn sponsors): double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::AccountId => u128;
+		// The donations not refunded yet of an ended round, starts from `pre_tax_support_pool` at the first claim
+		RefundBases get(fn refund_base): map hasher(blake2_128_concat) u32 => Option<u128>;
+		// The matching sub-pools of a round funded by directed donations
+		DirectedPools get(fn directed_pools): map hasher(blake2_128_concat) u32 => Vec<DirectedPool<T::Hash>>;
+		// Tags of a project in a round, which directed donations can target
+		ProjectTags get(fn project_tags): double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::Hash => Vec<Vec<u8>>;
+		// The tracks of a round, a project in a track is only matched from the pool of its track
+		Tracks get(fn tracks): map hasher(blake2_128_concat) u32 => Vec<Track>;
+		// The cost of votes locked by an account in a round with `VoteMode::Lock`, until it unlocks them after the round
+		LockedVotes get(fn locked_votes): double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::AccountId => u128;
+		// The sum of the locked votes of an account in all rounds, which is the amount of its `VoteLockId` lock
+		TotalLocked get(fn total_locked): map hasher(blake2_128_concat) T::AccountId => u128;
+		// The voice credits left to an account in a round with `VoteMode::Credits`, `None` until they are granted
+		VoiceCredits get(fn voice_credits): double_map hasher(blake2_128_concat) u32, hasher(blake2_128_concat) T::AccountId => Option<u128>;
+		// The total contributed by an account to a project in a round with `VoteMode::Contribution`, using vote_hash as the first key
+		Contributions get(fn contributions): double_map hasher(blake2_128_concat) T::Hash, hasher(blake2_128_concat) T::AccountId => u128;
+		// The layout of the storage, chains started before `Releases::V2_0_0` are migrated by `on_runtime_upgrade`
+		StorageVersion get(fn storage_version) build(|_| Releases::V2_0_0): Releases;
 	}
 	add_extra_genesis {
 		build(|_config| {
@@ -115,7 +510,7 @@ decl_storage! {
 // Pallets use events to inform users when important changes are made.
 // https://substrate.dev/docs/en/knowledgebase/runtime/events
 decl_event!(
-	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Hash =  <T as frame_system::Trait>::Hash, {
+	pub enum Event<T> where AccountId = <T as frame_system::Trait>::AccountId, Hash =  <T as frame_system::Trait>::Hash, Balance = BalanceOf<T>, {
 		/// Event documentation should end with an array that provides descriptive names for event
 		/// parameters. [project_hash, who]
 		ProjectRegistered(Hash, AccountId),
@@ -123,12 +518,68 @@ decl_event!(
 		VoteCost(Hash, u128),
 		/// parameters. [project_hash, who, number of ballots]
 		VoteSucceed(Hash, AccountId, u128),
+		/// parameters. [project_hash, who, ballot]
+		VoteAgainstSucceed(Hash, AccountId, u128),
+		/// parameters. [project_hash, who, ballot, refund]
+		VoteWithdrawn(Hash, AccountId, u128, u128),
 		/// parameters. [round_id]
 		RoundStarted(u32),
 		/// parameters. [round_id]
 		RoundEnded(u32),
+		/// parameters. [round_id, amount of tax]
+		TaxPaid(u32, u128),
+		/// parameters. [round_id]
+		RoundCancelled(u32),
+		/// parameters. [default round parameters, round deposit]
+		ParametersChanged(RoundParams, Balance),
 		/// parameters. [round_id, who, amount]
 		DonateSucceed(u32, AccountId, u128),
+		/// parameters. [round_id, amount]
+		TreasuryFunded(u32, u128),
+		/// parameters. [round_id]
+		DesignatedRoundSet(Option<u32>),
+		/// parameters. [round_id, amount]
+		PoolFunded(u32, u128),
+		/// parameters. [round_id, successor]
+		SuccessorSet(u32, Option<u32>),
+		/// parameters. [round_id, unallocated amount]
+		Unallocated(u32, u128),
+		/// parameters. [from round_id, to round_id, amount]
+		RolledOver(u32, u32, u128),
+		/// parameters. [round_id, who, amount]
+		SponsorRefunded(u32, AccountId, u128),
+		/// parameters. [round_id, project_hash, who, amount]
+		VoteRefunded(u32, Hash, AccountId, u128),
+		/// parameters. [round_id, who, index of the directed pool, amount]
+		DirectedDonateSucceed(u32, AccountId, u32, u128),
+		/// parameters. [round_id, project_hash]
+		ProjectTagsSet(u32, Hash),
+		/// parameters. [round_id, who, credits]
+		CreditsAllocated(u32, AccountId, u128),
+		/// parameters. [project_hash, who, amount]
+		ContributeSucceed(Hash, AccountId, u128),
+		/// parameters. [round_id, track]
+		TrackAdded(u32, u32),
+		/// parameters. [round_id, track, who, amount]
+		TrackDonateSucceed(u32, u32, AccountId, u128),
+		/// parameters. [round_id, track, support_pool, total_support_area]
+		TrackSettled(u32, u32, u128, u128),
+		/// parameters. [round_id, who]
+		VoterVerified(u32, AccountId),
+		/// parameters. [round_id]
+		IdentityWeightsSet(u32),
+		/// parameters. [round_id, policy]
+		SelfVotePolicySet(u32, SelfVotePolicy),
+		/// parameters. [project_hash, member]
+		ProjectMemberAdded(Hash, AccountId),
+		/// parameters. [project_hash, member]
+		ProjectMemberRemoved(Hash, AccountId),
+		/// parameters. [round_id, manager]
+		RoundManagerAdded(u32, AccountId),
+		/// parameters. [round_id, manager]
+		RoundManagerRemoved(u32, AccountId),
+		/// parameters. [round_id, who, amount]
+		VotesUnlocked(u32, AccountId, u128),
 	}
 );
 
@@ -145,10 +596,45 @@ decl_error! {
 		ProjectNameTooShort,
 		InvalidBallot,
 		DonationTooSmall,
-		RoundExisted,
 		RoundNotExist,
 		RoundHasEnded,
-		DuplicateRound,
+		VoterNotEligible,
+		NotInAllowlist,
+		InvalidParams,
+		SelfVoteForbidden,
+		NotProjectOwner,
+		RoundManagerExisted,
+		RoundManagerNotExist,
+		RoundNotEnded,
+		InvalidSuccessor,
+		NoUnallocatedFunds,
+		NotSponsor,
+		InvalidTargets,
+		TooManyDirectedPools,
+		InvalidTags,
+		TrackNotExist,
+		TooManyTracks,
+		AssetMismatch,
+		InsufficientBalanceToLock,
+		NotCreditRound,
+		InsufficientCredits,
+		WrongVoteMode,
+		NegativeVotesDisabled,
+		ConflictingVote,
+		InsufficientBallots,
+		InvalidBatch,
+		BudgetTooLow,
+		InvalidContribution,
+		NoVoteRefund,
+		AmountOverflow,
+		TooManyProjects,
+		TagFrozen,
+		TooManyCredits,
+		VotingStarted,
+		IdentityWeightTooHigh,
+		RoundExisted,
+		NothingLocked,
+		TooManyRoundManagers,
 	}
 }
 
@@ -162,11 +648,40 @@ decl_module! {
 
 		// Events must be initialized if they are used by the pallet.
 		fn deposit_event() = default;
-		const UnitOfVote: u128 = T::UnitOfVote::get();
-		const NumberOfUnitPerVote: u128 = T::NumberOfUnitPerVote::get();
-		const FeeRatioPerVote: u128 = T::FeeRatioPerVote::get();
-		const NameMinLength: u32 = T::NameMinLength::get() as u32;
-		const NameMaxLength: u32 = T::NameMaxLength::get() as u32;
+
+		fn on_runtime_upgrade() -> Weight {
+			// the rounds must be readable to seed the next id
+			let weight = migrations::migrate_to_v2::<T>();
+			weight.saturating_add(migrations::seed_next_round_id::<T>())
+		}
+
+		/// Change the pallet-wide defaults, rounds already started keep their parameters
+		#[weight = 10_000 + T::DbWeight::get().writes(1)]
+		pub fn set_parameters(origin, parameters: ParametersOf<T>) -> dispatch::DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			// the new defaults must be within the new bounds
+			Self::validate_params(&parameters.round, &parameters.bounds)?;
+			GlobalParameters::<T>::put(parameters.clone());
+			Self::deposit_event(RawEvent::ParametersChanged(parameters.round, parameters.round_deposit));
+			Ok(())
+		}
+
+		/// Choose the round whose pool receives the imbalances given to this pallet (such as the `fee_share`
+		/// of transaction fees), with `None` they are burned
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+		pub fn set_designated_round(origin, round_id: Option<u32>) -> dispatch::DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			if let Some(id) = round_id {
+				ensure!(Rounds::contains_key(&id), Error::<T>::RoundNotExist);
+				let round = Rounds::get(id);
+				ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+				// imbalances are in the native currency
+				ensure!(round.params.matching_asset == AssetId::Native, Error::<T>::AssetMismatch);
+			}
+			DesignatedRound::set(round_id);
+			Self::deposit_event(RawEvent::DesignatedRoundSet(round_id));
+			Ok(())
+		}
 
 		/// A round gets sponsored, this will transfer from sponsor's account to our internal account with the amount to be sponsored
 		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
@@ -176,11 +691,11 @@ decl_module! {
 			let round = Rounds::get(round_id);
 			ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
 			// the minimum unit, make sure the donate is greater than this
-			let min_unit_number = Self::cal_amount(1u128, false);
+			let min_unit_number = Self::cal_amount(&round.params, 1u128, false)?;
 			let amount_number = Self::balance_to_u128(amount);
-			let fee_number = T::FeeRatioPerVote::get().checked_mul(amount_number / T::NumberOfUnitPerVote::get()).unwrap();
+			let fee_number = round.params.fee_ratio_per_vote.checked_mul(amount_number / round.params.number_of_unit_per_vote).unwrap();
 			ensure!(amount_number > min_unit_number, Error::<T>::DonationTooSmall);
-			let _ = T::Currency::transfer(&who, &Self::account_id(), amount, KeepAlive);
+			T::Assets::transfer(round.params.matching_asset, &who, &Self::account_id(), amount)?;
 			// update the round
 			Rounds::mutate(round_id, |rnd| {
 				let ptsp = rnd.pre_tax_support_pool;
@@ -190,118 +705,612 @@ decl_module! {
 				rnd.support_pool = (amount_number-fee_number).checked_add(sp).unwrap();
 				rnd.total_tax = fee_number.checked_add(tt).unwrap();
 			});
+			Sponsors::<T>::mutate(round_id, &who, |donated| *donated = donated.saturating_add(amount_number));
 			Self::deposit_event(RawEvent::DonateSucceed(round_id, who, Self::balance_to_u128(amount)));
 			Ok(())
 		}
 
-		/// Create a new round, make sure to use a fresh index, any used index is not allowed, even those ended
-		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
-		pub fn start_round(origin, round_id: u32) -> dispatch::DispatchResult {
-			// Only amdin can control the round 
+		/// Donate to a sub-pool of a round, which only matches the projects in `targets` (by hash or by tag)
+		/// Donations with the same targets share a sub-pool
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,3)]
+		pub fn donate_directed(
+			origin,
+			round_id: u32,
+			targets: Vec<DirectedTarget<T::Hash>>,
+			#[compact] amount: BalanceOf<T>
+		) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let round = Rounds::get(round_id);
+			ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
+			ensure!(!targets.is_empty() && targets.len() <= MAX_TARGETS, Error::<T>::InvalidTargets);
+			let min_unit_number = Self::cal_amount(&round.params, 1u128, false)?;
+			let amount_number = Self::balance_to_u128(amount);
+			let fee_number = round.params.fee_ratio_per_vote.checked_mul(amount_number / round.params.number_of_unit_per_vote).unwrap();
+			ensure!(amount_number > min_unit_number, Error::<T>::DonationTooSmall);
+			let mut pools = DirectedPools::<T>::get(round_id);
+			let index = match pools.iter().position(|pool| pool.targets == targets) {
+				Some(index) => index,
+				None => {
+					ensure!(pools.len() < MAX_DIRECTED_POOLS, Error::<T>::TooManyDirectedPools);
+					pools.push(DirectedPool { targets, amount: 0 });
+					pools.len() - 1
+				}
+			};
+			T::Assets::transfer(round.params.matching_asset, &who, &Self::account_id(), amount)?;
+			pools[index].amount = pools[index].amount.checked_add(amount_number - fee_number).unwrap();
+			DirectedPools::<T>::insert(round_id, pools);
+			Rounds::mutate(round_id, |rnd| {
+				rnd.pre_tax_support_pool = rnd.pre_tax_support_pool.checked_add(amount_number).unwrap();
+				rnd.total_tax = rnd.total_tax.checked_add(fee_number).unwrap();
+			});
+			Sponsors::<T>::mutate(round_id, &who, |donated| *donated = donated.saturating_add(amount_number));
+			Self::deposit_event(RawEvent::DirectedDonateSucceed(round_id, who, index as u32, amount_number));
+			Ok(())
+		}
+
+		/// Set the voice credits of some voters of an `ongoing` round with `VoteMode::Credits` before its first vote,
+		/// at most `credits_per_voter`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1 + MAX_PROJECTS as u64, allocations.len() as u64)]
+		pub fn allocate_credits(origin, round_id: u32, allocations: Vec<(T::AccountId, u128)>) -> dispatch::DispatchResult {
 			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let round = Rounds::get(round_id);
+			ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+			ensure!(round.params.vote_mode == VoteMode::Credits, Error::<T>::NotCreditRound);
+			ensure!(
+				allocations.iter().all(|(_, credits)| *credits <= round.params.credits_per_voter),
+				Error::<T>::TooManyCredits
+			);
+			// the budgets are fixed once voting has started
+			ensure!(
+				!Projects::<T>::iter_prefix_values(round_id).any(|project| project.total_votes > 0 || project.votes_against > 0),
+				Error::<T>::VotingStarted
+			);
+			for (who, credits) in allocations {
+				VoiceCredits::<T>::insert(round_id, &who, credits);
+				Self::deposit_event(RawEvent::CreditsAllocated(round_id, who, credits));
+			}
+			Ok(())
+		}
+
+		/// Add a named track to an `ongoing` round, projects registered in it are only matched from its pool
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+		pub fn add_track(origin, round_id: u32, name: Vec<u8>) -> dispatch::DispatchResult {
+			Self::ensure_round_manager(origin, round_id)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let round = Rounds::get(round_id);
+			ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+			ensure!(name.len() >= round.params.name_min_length as usize, Error::<T>::ProjectNameTooShort);
+			ensure!(name.len() <= round.params.name_max_length as usize, Error::<T>::ProjectNameTooLong);
+			let mut tracks = Tracks::get(round_id);
+			ensure!(tracks.len() < MAX_TRACKS, Error::<T>::TooManyTracks);
+			tracks.push(Track { name, ..Default::default() });
+			let track = (tracks.len() - 1) as u32;
+			Tracks::insert(round_id, tracks);
+			Self::deposit_event(RawEvent::TrackAdded(round_id, track));
+			Ok(())
+		}
+
+		/// Donate to the pool of a track, taxed as `donate`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,3)]
+		pub fn donate_to_track(origin, round_id: u32, track: u32, #[compact] amount: BalanceOf<T>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let round = Rounds::get(round_id);
+			ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
+			let mut tracks = Tracks::get(round_id);
+			ensure!((track as usize) < tracks.len(), Error::<T>::TrackNotExist);
+			let min_unit_number = Self::cal_amount(&round.params, 1u128, false)?;
+			let amount_number = Self::balance_to_u128(amount);
+			let fee_number = round.params.fee_ratio_per_vote.checked_mul(amount_number / round.params.number_of_unit_per_vote).unwrap();
+			ensure!(amount_number > min_unit_number, Error::<T>::DonationTooSmall);
+			T::Assets::transfer(round.params.matching_asset, &who, &Self::account_id(), amount)?;
+			let t = &mut tracks[track as usize];
+			t.pre_tax_support_pool = t.pre_tax_support_pool.checked_add(amount_number).unwrap();
+			t.support_pool = t.support_pool.checked_add(amount_number - fee_number).unwrap();
+			Tracks::insert(round_id, tracks);
+			Rounds::mutate(round_id, |rnd| {
+				rnd.pre_tax_support_pool = rnd.pre_tax_support_pool.checked_add(amount_number).unwrap();
+				rnd.total_tax = rnd.total_tax.checked_add(fee_number).unwrap();
+			});
+			Sponsors::<T>::mutate(round_id, &who, |donated| *donated = donated.saturating_add(amount_number));
+			Self::deposit_event(RawEvent::TrackDonateSucceed(round_id, track, who, amount_number));
+			Ok(())
+		}
+
+		/// Tag a project, so that directed donations to the tag can match it.
+		/// Once a directed donation targets a tag, projects can neither join nor leave it
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(4,1)]
+		pub fn set_project_tags(origin, round_id: u32, hash: T::Hash, tags: Vec<Vec<u8>>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Projects::<T>::contains_key(&round_id, &hash), Error::<T>::ProjectNotExist);
+			ensure!(Projects::<T>::get(round_id, &hash).owner == who, Error::<T>::NotProjectOwner);
+			let round = Rounds::get(round_id);
+			ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+			let max_length = round.params.name_max_length as usize;
+			ensure!(tags.len() <= MAX_TAGS && tags.iter().all(|tag| tag.len() <= max_length), Error::<T>::InvalidTags);
+			let old = ProjectTags::<T>::get(round_id, &hash);
+			let pools = DirectedPools::<T>::get(round_id);
+			let mut changed = tags.iter().filter(|tag| !old.contains(tag)).chain(old.iter().filter(|tag| !tags.contains(tag)));
+			ensure!(
+				!changed.any(|tag| pools.iter().any(|pool| pool.targets.contains(&DirectedTarget::Tag(tag.clone())))),
+				Error::<T>::TagFrozen
+			);
+			ProjectTags::<T>::insert(round_id, &hash, tags);
+			Self::deposit_event(RawEvent::ProjectTagsSet(round_id, hash));
+			Ok(())
+		}
+
+		/// Move funds from the treasury to the pool of an `ongoing` round, no tax is charged as it would go back to the treasury
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+		pub fn fund_round_from_treasury(origin, round_id: u32, #[compact] amount: BalanceOf<T>) -> dispatch::DispatchResult {
+			T::TreasuryOrigin::ensure_origin(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let round = Rounds::get(round_id);
+			ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
+			let amount_number = Self::balance_to_u128(amount);
+			ensure!(amount_number > 0, Error::<T>::DonationTooSmall);
+			T::Assets::transfer(round.params.matching_asset, &T::TreasuryAccount::get(), &Self::account_id(), amount)?;
+			Rounds::mutate(round_id, |rnd| {
+				rnd.pre_tax_support_pool = rnd.pre_tax_support_pool.checked_add(amount_number).unwrap();
+				rnd.support_pool = rnd.support_pool.checked_add(amount_number).unwrap();
+			});
+			Self::deposit_event(RawEvent::TreasuryFunded(round_id, amount_number));
+			Ok(())
+		}
+
+		/// Create a new round with the id from `NextRoundId`
+		/// Any account can open a round by reserving `RoundDeposit`, it becomes the operator and a manager of the round
+		/// If `allowlist` is given, only accounts included in this merkle root can vote
+		/// `managers` can control the round as AdminOrigin does, at most `MAX_ROUND_MANAGERS` without duplicates
+		/// `params` overrides the default parameters, within the bounds checked by `validate_params`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,5) + 1_000 * managers.len() as Weight]
+		pub fn start_round(
+			origin,
+			allowlist: Option<T::Hash>,
+			mut managers: Vec<T::AccountId>,
+			params: Option<RoundParams>,
+		) -> dispatch::DispatchResult {
+			// admin opens a round for free
+			let operator = match T::AdminOrigin::try_origin(origin) {
+				Ok(_) => None,
+				Err(origin) => Some(ensure_signed(origin)?),
+			};
+			let parameters = Self::parameters();
+			let params = params.unwrap_or(parameters.round);
+			Self::validate_params(&params, &parameters.bounds)?;
+			for (index, manager) in managers.iter().enumerate() {
+				ensure!(!managers[..index].contains(manager), Error::<T>::RoundManagerExisted);
+			}
+			if let Some(who) = &operator {
+				if !managers.contains(who) {
+					managers.push(who.clone());
+				}
+			}
+			ensure!(managers.len() <= MAX_ROUND_MANAGERS, Error::<T>::TooManyRoundManagers);
+			let round_id = NextRoundId::get();
+			// rounds opened with an explicit id before `NextRoundId` can not be overwritten
 			ensure!(!Rounds::contains_key(&round_id), Error::<T>::RoundExisted);
+			let next_round_id = round_id.checked_add(1).ok_or(Error::<T>::StorageOverflow)?;
+			if let Some(who) = operator {
+				let deposit = parameters.round_deposit;
+				T::Currency::reserve(&who, deposit)?;
+				RoundOperators::<T>::insert(round_id, (who, deposit));
+			}
+			NextRoundId::put(next_round_id);
 			let round = Round {
 				ongoing: true,
 				support_pool: 0,
 				pre_tax_support_pool: 0,
 				total_support_area: 0,
-				total_tax: 0
+				total_tax: 0,
+				params,
+				tax_paid: 0,
+				unallocated: 0,
+				vote_tax: 0,
+				unallocated_votes: 0,
+				votes_to_pool: 0,
+				voter_refunds: 0,
+				project_count: 0,
+				vote_tax_paid: 0,
 			};
 			Rounds::insert(round_id, round);
+			if let Some(root) = allowlist {
+				Allowlists::<T>::insert(round_id, root);
+			}
+			if !managers.is_empty() {
+				RoundManagers::<T>::insert(round_id, managers);
+			}
 			Self::deposit_event(RawEvent::RoundStarted(round_id));
 			Ok(())
 		}
 
 		/// End an `ongoing` round and distribute the funds in sponsor pool, any invalid index or round status will cause errors
-		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1 + MAX_PROJECTS as u64, 1 + MAX_PROJECTS as u64)]
 		pub fn end_round(origin, round_id: u32) -> dispatch::DispatchResult {
-			// Only amdin can control the round 
-			T::AdminOrigin::ensure_origin(origin)?;
+			// Only admin or managers can control the round
+			Self::ensure_round_manager(origin, round_id)?;
 			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
 			let mut round = Rounds::get(round_id);
 			ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
-			let area = round.total_support_area;
-			let pool = round.support_pool;
-			for (hash, mut project) in Projects::<T>::iter_prefix(round_id) {
-				if area > 0 {
-					let total = project.grants;
-					project.grants = total.checked_add(
-						project.support_area.checked_mul(pool/area).unwrap()
-					).unwrap();
+			let params = round.params.clone();
+			// everything owed for this round in each asset, what is not paid out below stays unallocated
+			let (mut unallocated, mut unallocated_votes) = Self::round_obligations(round_id, &round);
+			// move the tax out before paying the grants, each part in the asset it was collected in
+			let donation_tax = round.total_tax.saturating_sub(round.vote_tax);
+			if Self::pay_tax(params.matching_asset, donation_tax) {
+				round.tax_paid = round.tax_paid.saturating_add(donation_tax);
+				unallocated = unallocated.saturating_sub(donation_tax);
+			}
+			if Self::pay_tax(params.vote_asset, round.vote_tax) {
+				round.tax_paid = round.tax_paid.saturating_add(round.vote_tax);
+				round.vote_tax_paid = round.vote_tax;
+				unallocated_votes = unallocated_votes.saturating_sub(round.vote_tax);
+			}
+			if round.tax_paid > 0 {
+				Self::deposit_event(RawEvent::TaxPaid(round_id, round.tax_paid));
+			}
+			for (hash, mut project, sources) in Self::matching(round_id, &round) {
+				let matching = sources.iter().fold(0u128, |total, (_, m)| total.checked_add(*m).unwrap());
+				// reckon the final grants, the votes and the matching are paid separately in their assets,
+				// a failed transfer is left unallocated
+				let due = project.grants.saturating_sub(project.withdrew);
+				if Self::pay_out(params.vote_asset, &project.owner, due) {
+					project.withdrew = project.withdrew.saturating_add(due);
+					unallocated_votes = unallocated_votes.saturating_sub(due);
 				}
+				if Self::pay_out(params.matching_asset, &project.owner, matching) {
+					project.withdrew = project.withdrew.saturating_add(matching);
+					unallocated = unallocated.saturating_sub(matching);
+				}
+				project.grants = project.grants.checked_add(matching).unwrap();
+				project.matching = matching;
 				debug::info!("Hash: {:?}, Total votes: {:?}, Grants: {:?}", hash, project.total_votes, project.grants);
-				// reckon the final grants
-				let _ = T::Currency::transfer(
-					&Self::account_id(),
-					&project.owner,
-					Self::u128_to_balance(project.grants),
-					KeepAlive
-				);
+				Projects::<T>::insert(round_id, hash, project);
+			}
+			// with a single asset, everything left can be rolled over or refunded
+			if params.vote_asset == params.matching_asset {
+				unallocated = unallocated.saturating_add(unallocated_votes);
+				unallocated_votes = 0;
+			}
+			round.unallocated_votes = unallocated_votes;
+			for (track, t) in Tracks::get(round_id).into_iter().enumerate() {
+				Self::deposit_event(RawEvent::TrackSettled(round_id, track as u32, t.support_pool, t.total_support_area));
 			}
 			round.ongoing = false;
+			round.unallocated = unallocated;
+			Self::deposit_event(RawEvent::Unallocated(round_id, unallocated));
+			let same_asset = |id: &u32| {
+				let successor = Rounds::get(*id);
+				successor.ongoing && (successor.params.matching_asset == params.matching_asset
+					|| successor.params.matching_asset == params.vote_asset)
+			};
+			if let Some(successor) = Successors::get(round_id).filter(same_asset) {
+				Self::roll_over(round_id, &mut round, successor);
+			}
 			Rounds::insert(round_id, round);
+			// return the deposit after a successful settlement
+			if let Some((operator, deposit)) = RoundOperators::<T>::take(round_id) {
+				T::Currency::unreserve(&operator, deposit);
+			}
 			Self::deposit_event(RawEvent::RoundEnded(round_id));
 			Ok(())
 		}
 
-		/// Register a project in an ongoing round, so that it can be voted
+		/// Take down an `ongoing` round without distributing its funds, the deposit of the operator is slashed.
+		/// Only the funds of sponsors are left unallocated, the payments of votes are kept for their voters
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+		pub fn cancel_round(origin, round_id: u32) -> dispatch::DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let mut round = Rounds::get(round_id);
+			ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+			let (unallocated, votes) = Self::round_obligations(round_id, &round);
+			// votes against a project paid to the pool go back to their voters as well
+			round.unallocated = unallocated.saturating_sub(round.votes_to_pool);
+			round.voter_refunds = votes.saturating_sub(round.vote_tax).saturating_add(round.votes_to_pool);
+			// the fee of votes is kept, as for unvote
+			if Self::pay_tax(round.params.vote_asset, round.vote_tax) {
+				round.tax_paid = round.vote_tax;
+				round.vote_tax_paid = round.vote_tax;
+				if round.tax_paid > 0 {
+					Self::deposit_event(RawEvent::TaxPaid(round_id, round.tax_paid));
+				}
+			} else {
+				round.unallocated_votes = round.vote_tax;
+			}
+			round.ongoing = false;
+			Rounds::insert(round_id, round);
+			if let Some((operator, deposit)) = RoundOperators::<T>::take(round_id) {
+				let (imbalance, _) = T::Currency::slash_reserved(&operator, deposit);
+				T::Slashed::on_unbalanced(imbalance);
+			}
+			Self::deposit_event(RawEvent::RoundCancelled(round_id));
+			Ok(())
+		}
+
+		/// Choose the round which receives the unallocated funds of `round_id` when it is settled
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+		pub fn set_successor(origin, round_id: u32, successor: Option<u32>) -> dispatch::DispatchResult {
+			Self::ensure_round_manager(origin, round_id)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			if let Some(id) = successor {
+				ensure!(id != round_id && Rounds::contains_key(&id), Error::<T>::InvalidSuccessor);
+				let params = Rounds::get(round_id).params;
+				let asset = Rounds::get(id).params.matching_asset;
+				ensure!(asset == params.matching_asset || asset == params.vote_asset, Error::<T>::AssetMismatch);
+			}
+			Successors::mutate(round_id, |s| *s = successor);
+			Self::deposit_event(RawEvent::SuccessorSet(round_id, successor));
+			Ok(())
+		}
+
+		/// Move the unallocated funds of an ended round to the pool of an `ongoing` round,
+		/// those in the matching asset of `successor`, which can be the vote asset of `round_id`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+		pub fn rollover(origin, round_id: u32, successor: u32) -> dispatch::DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let mut round = Rounds::get(round_id);
+			ensure!(!round.ongoing, Error::<T>::RoundNotEnded);
+			ensure!(round.unallocated > 0 || round.unallocated_votes > 0, Error::<T>::NoUnallocatedFunds);
+			ensure!(Rounds::contains_key(&successor), Error::<T>::RoundNotExist);
+			ensure!(Rounds::get(successor).ongoing, Error::<T>::RoundHasEnded);
+			let asset = Rounds::get(successor).params.matching_asset;
+			ensure!(asset == round.params.matching_asset || asset == round.params.vote_asset, Error::<T>::AssetMismatch);
+			let matching = if asset == round.params.matching_asset { round.unallocated } else { 0 };
+			let votes = if asset == round.params.vote_asset { round.unallocated_votes } else { 0 };
+			ensure!(matching > 0 || votes > 0, Error::<T>::NoUnallocatedFunds);
+			Self::roll_over(round_id, &mut round, successor);
+			Rounds::insert(round_id, round);
+			Ok(())
+		}
+
+		/// Take back the pro-rata share of the unallocated funds of an ended round, by what the caller donated to it
+		/// Funds from the treasury, fees or other rounds count in the base, their share stays unallocated
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+		pub fn claim_sponsor_refund(origin, round_id: u32) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let mut round = Rounds::get(round_id);
+			ensure!(!round.ongoing, Error::<T>::RoundNotEnded);
+			let donated = Sponsors::<T>::get(round_id, &who);
+			ensure!(donated > 0, Error::<T>::NotSponsor);
+			ensure!(round.unallocated > 0, Error::<T>::NoUnallocatedFunds);
+			let base = RefundBases::get(round_id).unwrap_or(round.pre_tax_support_pool);
+			// the base includes every donation, so the share never exceeds the unallocated funds
+			let refund = multiply_by_rational(round.unallocated, donated, base.max(donated))
+				.map_err(|_| Error::<T>::AmountOverflow)?;
+			T::Assets::transfer(round.params.matching_asset, &Self::account_id(), &who, Self::u128_to_balance(refund))?;
+			round.unallocated -= refund;
+			Rounds::insert(round_id, round);
+			RefundBases::insert(round_id, base.saturating_sub(donated));
+			Sponsors::<T>::remove(round_id, &who);
+			Self::deposit_event(RawEvent::SponsorRefunded(round_id, who, refund));
+			Ok(())
+		}
+
+		/// Take back what was paid for votes to a project of a cancelled round, minus the fee
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+		pub fn claim_vote_refund(origin, round_id: u32, hash: T::Hash) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let mut round = Rounds::get(round_id);
+			ensure!(!round.ongoing, Error::<T>::RoundNotEnded);
+			let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
+			let refund = Self::vote_payment(&round.params, &vote_hash, &who)?;
+			// only a cancelled round has voter refunds
+			ensure!(refund > 0 && refund <= round.voter_refunds, Error::<T>::NoVoteRefund);
+			T::Assets::transfer(round.params.vote_asset, &Self::account_id(), &who, Self::u128_to_balance(refund))?;
+			round.voter_refunds -= refund;
+			Rounds::insert(round_id, round);
+			ProjectVotes::<T>::remove(vote_hash, &who);
+			Contributions::<T>::remove(vote_hash, &who);
+			Self::deposit_event(RawEvent::VoteRefunded(round_id, hash, who, refund));
+			Ok(())
+		}
+
+		/// Release the votes locked by the caller in a round once it has ended or been cancelled.
+		/// Each voter unlocks its own votes, so that settling a round does not depend on how many voted
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3,2)]
+		pub fn unlock(origin, round_id: u32) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			ensure!(!Rounds::get(round_id).ongoing, Error::<T>::RoundNotEnded);
+			let amount = LockedVotes::<T>::take(round_id, &who);
+			ensure!(amount > 0, Error::<T>::NothingLocked);
+			Self::reduce_lock(&who, amount);
+			Self::deposit_event(RawEvent::VotesUnlocked(round_id, who, amount));
+			Ok(())
+		}
+
+		/// Add a manager to a round, only admin or the current managers can do this
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1) + 1_000 * MAX_ROUND_MANAGERS as Weight]
+		pub fn add_round_manager(origin, round_id: u32, manager: T::AccountId) -> dispatch::DispatchResult {
+			Self::ensure_round_manager(origin, round_id)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let mut managers = RoundManagers::<T>::get(round_id);
+			ensure!(!managers.contains(&manager), Error::<T>::RoundManagerExisted);
+			ensure!(managers.len() < MAX_ROUND_MANAGERS, Error::<T>::TooManyRoundManagers);
+			managers.push(manager.clone());
+			RoundManagers::<T>::insert(round_id, managers);
+			Self::deposit_event(RawEvent::RoundManagerAdded(round_id, manager));
+			Ok(())
+		}
+
+		/// Remove a manager from a round, only admin or the current managers can do this
 		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
-		pub fn register_project(origin, round_id: u32, hash: T::Hash, name: Vec<u8>) -> dispatch::DispatchResult {
+		pub fn remove_round_manager(origin, round_id: u32, manager: T::AccountId) -> dispatch::DispatchResult {
+			Self::ensure_round_manager(origin, round_id)?;
+			let mut managers = RoundManagers::<T>::get(round_id);
+			ensure!(managers.contains(&manager), Error::<T>::RoundManagerNotExist);
+			managers.retain(|m| m != &manager);
+			RoundManagers::<T>::insert(round_id, managers);
+			Self::deposit_event(RawEvent::RoundManagerRemoved(round_id, manager));
+			Ok(())
+		}
+
+		/// Set the multiplier of each identity level for an ongoing round, at most `max_identity_weight`
+		/// A voter's weight is fixed at the first vote to a project, so changes only apply to later voters
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,1)]
+		pub fn set_identity_weights(origin, round_id: u32, weights: Vec<(IdentityLevel, u32)>) -> dispatch::DispatchResult {
+			Self::ensure_round_manager(origin, round_id)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			ensure!(Rounds::get(round_id).ongoing, Error::<T>::RoundHasEnded);
+			let max_weight = Self::parameters().bounds.max_identity_weight;
+			ensure!(weights.iter().all(|(_, weight)| *weight <= max_weight), Error::<T>::IdentityWeightTooHigh);
+			IdentityWeights::insert(round_id, weights);
+			Self::deposit_event(RawEvent::IdentityWeightsSet(round_id));
+			Ok(())
+		}
+
+		/// Set how votes from a project's own team are treated in an ongoing round
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
+		pub fn set_self_vote_policy(origin, round_id: u32, policy: SelfVotePolicy) -> dispatch::DispatchResult {
+			Self::ensure_round_manager(origin, round_id)?;
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			ensure!(Rounds::get(round_id).ongoing, Error::<T>::RoundHasEnded);
+			SelfVotePolicies::insert(round_id, policy);
+			Self::deposit_event(RawEvent::SelfVotePolicySet(round_id, policy));
+			Ok(())
+		}
+
+		/// Register an account as a team member of your project, its votes are treated as self votes.
+		/// The team is frozen once the project has a vote
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
+		pub fn add_project_member(origin, round_id: u32, hash: T::Hash, member: T::AccountId) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			let vote_hash = Self::open_team(round_id, hash, &who)?;
+			ProjectMembers::<T>::insert(vote_hash, &member, true);
+			Self::deposit_event(RawEvent::ProjectMemberAdded(hash, member));
+			Ok(())
+		}
+
+		/// Remove a team member of your project, before it has a vote
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3,1)]
+		pub fn remove_project_member(origin, round_id: u32, hash: T::Hash, member: T::AccountId) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			let vote_hash = Self::open_team(round_id, hash, &who)?;
+			ProjectMembers::<T>::remove(vote_hash, &member);
+			Self::deposit_event(RawEvent::ProjectMemberRemoved(hash, member));
+			Ok(())
+		}
+
+		/// Register a project in an ongoing round, so that it can be voted, a round has at most `MAX_PROJECTS`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+		pub fn register_project(origin, round_id: u32, hash: T::Hash, name: Vec<u8>, track: Option<u32>) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
-			ensure!(name.len() >= T::NameMinLength::get(), Error::<T>::ProjectNameTooShort);
-			ensure!(name.len() <= T::NameMaxLength::get(), Error::<T>::ProjectNameTooLong);
-			ensure!(Rounds::<T>::contains_key(&round_id), Error::<T>::RoundNotExist);
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let mut round = Rounds::get(round_id);
+			let params = &round.params;
+			ensure!(name.len() >= params.name_min_length as usize, Error::<T>::ProjectNameTooShort);
+			ensure!(name.len() <= params.name_max_length as usize, Error::<T>::ProjectNameTooLong);
 			ensure!(!Projects::<T>::contains_key(&round_id, &hash), Error::<T>::DuplicateProject);
+			ensure!(round.project_count < MAX_PROJECTS, Error::<T>::TooManyProjects);
+			if let Some(t) = track {
+				ensure!((t as usize) < Tracks::get(round_id).len(), Error::<T>::TrackNotExist);
+			}
 			let project = Project {
 				total_votes: 0,
+				weighted_votes: 0,
 				grants: 0,
 				support_area: 0,
 				withdrew: 0,
 				name: name,
 				owner: who.clone(),
+				discounted_votes: 0,
+				track,
+				votes_against: 0,
+				weighted_against: 0,
+				matching: 0,
 			};
 			Projects::<T>::insert(round_id, hash, project);
+			round.project_count += 1;
+			Rounds::insert(round_id, round);
 			Self::deposit_event(RawEvent::ProjectRegistered(hash, who));
 			Ok(())
 		}
 
 		/// Vote to a project, this function will transfer corresponding amount of token per your input ballot
-		#[weight = 10_000 + T::DbWeight::get().reads_writes(1,1)]
-		pub fn vote(origin, round_id: u32, hash: T::Hash, ballot: u128) -> dispatch::DispatchResult {
+		/// In a round with allowlist, `proof` is required for the first vote of an account
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+		pub fn vote(origin, round_id: u32, hash: T::Hash, ballot: u128, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_vote(who, round_id, hash, ballot, false, proof)
+		}
+
+		/// Vote against a project in a round which accepts negative votes, at the same quadratic cost.
+		/// The votes against a project reduce its support area, floored at zero
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+		pub fn vote_against(origin, round_id: u32, hash: T::Hash, ballot: u128, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_vote(who, round_id, hash, ballot, true, proof)
+		}
+
+		/// Vote to several projects of a round at once, paid by a single transfer (or lock, or credits).
+		/// Either all votes succeed or none, a project can only appear once
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2 * votes.len() as u64 + 1, 2 * votes.len() as u64 + 1)]
+		pub fn vote_many(origin, round_id: u32, votes: Vec<(T::Hash, u128)>, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
 			let who = ensure_signed(origin)?;
+			Self::do_vote_many(who, round_id, votes, proof)
+		}
+
+		/// Vote to projects in proportion to their `weights` with at most `budget` of the vote asset (or voice credits),
+		/// given the ballots the voter already has. The ballots are those of `allocate_budget`, voted as `vote_many`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3 * weights.len() as u64 + 1, 2 * weights.len() as u64 + 1)]
+		pub fn vote_with_budget(origin, round_id: u32, budget: u128, weights: Vec<(T::Hash, u32)>, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			ensure!(!weights.is_empty() && weights.len() <= MAX_BATCH_VOTES, Error::<T>::InvalidBatch);
+			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+			let points = Self::budget_points(&who, round_id, budget);
+			let votes = Self::allocate_points(&who, round_id, points, &weights);
+			ensure!(!votes.is_empty(), Error::<T>::BudgetTooLow);
+			Self::do_vote_many(who, round_id, votes, proof)
+		}
+
+		/// Take back some ballots for a project while the round is ongoing, the difference of quadratic cost is
+		/// refunded minus the fee (unlocked in lock mode, credited back in voice credits)
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(2,2)]
+		pub fn unvote(origin, round_id: u32, hash: T::Hash, ballot: u128) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_unvote(who, round_id, hash, ballot)
+		}
+
+		/// Move some ballots from a project to another of the same round, which is an `unvote` followed by a `vote`
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(4,4)]
+		#[transactional]
+		pub fn move_votes(origin, round_id: u32, from: T::Hash, to: T::Hash, ballot: u128) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			Self::do_unvote(who.clone(), round_id, from, ballot)?;
+			Self::do_vote(who, round_id, to, ballot, false, None)
+		}
+
+		/// Contribute any amount to a project in a round with `VoteMode::Contribution`, the contributions of an account
+		/// to a project count as their square root, so that the matching follows (Σ√cᵢ)²
+		#[weight = 10_000 + T::DbWeight::get().reads_writes(3,3)]
+		#[transactional]
+		pub fn contribute(origin, round_id: u32, hash: T::Hash, #[compact] amount: BalanceOf<T>, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
+			let who = ensure_signed(origin)?;
+			let amount_number = Self::balance_to_u128(amount);
+			ensure!(T::VoterEligibility::is_eligible(&who), Error::<T>::VoterNotEligible);
 			ensure!(Projects::<T>::contains_key(&round_id, &hash), Error::<T>::ProjectNotExist);
-			ensure!(ballot > 0, Error::<T>::InvalidBallot);
-			// check whether this round still ongoing
+			ensure!(amount_number > 0, Error::<T>::InvalidContribution);
 			ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
 			let round = Rounds::get(round_id);
-			ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
-
-			// need to calculate hash of project hash and round_id combination here to avoid conflicts of projects in different rounds
+			ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+			ensure!(round.params.vote_mode == VoteMode::Contribution, Error::<T>::WrongVoteMode);
+			let newly_verified = Self::check_allowlist(round_id, &who, proof)?;
 			let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
-			let voted = ProjectVotes::<T>::get(vote_hash, &who);
-			let cost = Self::cal_cost(voted, ballot);
-			let amount = Self::cal_amount(cost, false);
-			let fee = Self::cal_amount(cost, true);
-			// transfer first, update last, as transfer will ensure the free balance is enough
-			let _ = T::Currency::transfer(&who, &Self::account_id(), Self::u128_to_balance(amount), KeepAlive);
-
-			// update the project and corresponding round
-			ProjectVotes::<T>::insert(vote_hash, &who, ballot+voted);
-			Projects::<T>::mutate(round_id, hash, |poj| {
-				let support_area = ballot.checked_mul(poj.total_votes - voted).unwrap();
-				poj.support_area = support_area.checked_add(poj.support_area).unwrap();
-				poj.total_votes += ballot;
-				poj.grants += amount - fee;
-				debug::info!("Total votes: {:?}, Current votes: {:?}, Support Area: {:?},Est cost: {:?}",
-				poj.total_votes, voted, support_area, cost);
-				Rounds::mutate(round_id, |rnd| {
-					let tsa = rnd.total_support_area;
-					let tt = rnd.total_tax;
-					rnd.total_support_area = support_area.checked_add(tsa).unwrap();
-					rnd.total_tax = fee.checked_add(tt).unwrap();
-				});
-			});
-			Self::deposit_event(RawEvent::VoteSucceed(hash, who, ballot));
+			let record = Self::voter_record(round_id, &hash, &vote_hash, &who)?;
+			T::Assets::transfer(round.params.vote_asset, &who, &Self::account_id(), amount)?;
+
+			if newly_verified {
+				VerifiedVoters::<T>::insert(round_id, &who, true);
+				Self::deposit_event(RawEvent::VoterVerified(round_id, who.clone()));
+			}
+			Self::add_contribution(round_id, hash, vote_hash, &who, record, amount_number)?;
+			Self::deposit_event(RawEvent::ContributeSucceed(hash, who, amount_number));
 			Ok(())
 		}
 	}
@@ -318,23 +1327,579 @@ impl<T: Config> Module<T> {
 		T::ModuleId::get().into_account()
 	}
 
-	pub fn cal_cost(voted: u128, ballot: u128) -> u128 {
-		let mut points = ballot.checked_mul(ballot.checked_add(1).unwrap()).unwrap() / 2; 
-		points = points.checked_add(ballot.checked_mul(voted).unwrap()).unwrap();
-		return points;
+	/// Ensure the origin is AdminOrigin or a manager of the round
+	fn ensure_round_manager(origin: T::Origin, round_id: u32) -> dispatch::DispatchResult {
+		if T::AdminOrigin::try_origin(origin.clone()).is_ok() {
+			return Ok(());
+		}
+		let who = ensure_signed(origin)?;
+		ensure!(RoundManagers::<T>::get(round_id).contains(&who), dispatch::DispatchError::BadOrigin);
+		Ok(())
+	}
+
+	/// Check whether `who` can vote in a round with allowlist, return true if the proof is verified just now
+	/// so that the caller can record it once the vote succeeds
+	fn check_allowlist(round_id: u32, who: &T::AccountId, proof: Option<Vec<T::Hash>>) -> Result<bool, Error<T>> {
+		let root = match Allowlists::<T>::get(round_id) {
+			Some(root) => root,
+			None => return Ok(false),
+		};
+		if VerifiedVoters::<T>::get(round_id, who) {
+			return Ok(false);
+		}
+		let proof = proof.ok_or(Error::<T>::NotInAllowlist)?;
+		let leaf = merkle::leaf::<T::Hashing, _>(who);
+		ensure!(merkle::verify::<T::Hashing>(&root, leaf, &proof), Error::<T>::NotInAllowlist);
+		Ok(true)
+	}
+
+	/// The multiplier of `who` in a round according to its identity level
+	pub fn identity_weight(round_id: u32, who: &T::AccountId) -> u32 {
+		let level = T::IdentityLevels::identity_level(who);
+		IdentityWeights::get(round_id).into_iter()
+			.find(|(l, _)| *l == level)
+			.map_or(1, |(_, weight)| weight)
 	}
 
-	pub fn cal_amount(amount: u128, is_fee: bool) -> u128 {
-		let uov = T::UnitOfVote::get();
-		let nup = T::NumberOfUnitPerVote::get();
-		let frpv = T::FeeRatioPerVote::get();
-		if is_fee { 
-			uov.checked_mul(frpv).unwrap().checked_mul(amount).unwrap() 
+	/// The points of `ballot` more votes after `voted`, fails on a ballot too large to price
+	pub fn cal_cost(voted: u128, ballot: u128) -> Result<u128, Error<T>> {
+		ballot.checked_add(1)
+			.and_then(|next| ballot.checked_mul(next))
+			.and_then(|points| points.checked_div(2)?.checked_add(ballot.checked_mul(voted)?))
+			.ok_or(Error::<T>::AmountOverflow)
+	}
+
+	/// Units and credits must be within `bounds`, the fee can not exceed the vote amount,
+	/// and project names must fit in the `NameMaxLength` of the runtime
+	fn validate_params(params: &RoundParams, bounds: &ParamBounds) -> Result<(), Error<T>> {
+		let unit = params.unit_of_vote;
+		let number = params.number_of_unit_per_vote;
+		ensure!(unit >= bounds.min_unit_of_vote.max(1) && unit <= bounds.max_unit_of_vote, Error::<T>::InvalidParams);
+		ensure!(number >= bounds.min_number_of_unit_per_vote.max(1) && number <= bounds.max_number_of_unit_per_vote, Error::<T>::InvalidParams);
+		ensure!(unit.checked_mul(number).is_some(), Error::<T>::InvalidParams);
+		ensure!(params.fee_ratio_per_vote >= bounds.min_fee_ratio_per_vote, Error::<T>::InvalidParams);
+		ensure!(params.fee_ratio_per_vote <= number, Error::<T>::InvalidParams);
+		ensure!(params.credits_per_voter <= bounds.max_credits_per_voter, Error::<T>::InvalidParams);
+		ensure!(params.name_min_length <= params.name_max_length, Error::<T>::InvalidParams);
+		ensure!(params.name_max_length as usize <= T::NameMaxLength::get(), Error::<T>::InvalidParams);
+		// only the native currency can be locked
+		ensure!(params.vote_mode != VoteMode::Lock || params.vote_asset == AssetId::Native, Error::<T>::InvalidParams);
+		// contributions can not be against a project
+		ensure!(params.vote_mode != VoteMode::Contribution || params.negative_votes == NegativeVotes::Disabled, Error::<T>::InvalidParams);
+		// only paid votes in the native currency can be burned, votes can only go to a pool of the same asset
+		match params.negative_votes {
+			NegativeVotes::Disabled => {},
+			NegativeVotes::ToPool => ensure!(params.vote_asset == params.matching_asset, Error::<T>::InvalidParams),
+			NegativeVotes::Burn => ensure!(
+				params.vote_mode == VoteMode::Spend && params.vote_asset == AssetId::Native,
+				Error::<T>::InvalidParams
+			),
+		}
+		Ok(())
+	}
+
+	/// Add `amount` to the votes locked by `who` in a round, the lock can not exceed the free balance
+	fn lock_votes(round_id: u32, who: &T::AccountId, amount: u128) -> dispatch::DispatchResult {
+		let total = TotalLocked::<T>::get(who).saturating_add(amount);
+		ensure!(Self::balance_to_u128(T::Currency::free_balance(who)) >= total, Error::<T>::InsufficientBalanceToLock);
+		T::Currency::set_lock(T::VoteLockId::get(), who, Self::u128_to_balance(total), WithdrawReasons::all());
+		TotalLocked::<T>::insert(who, total);
+		LockedVotes::<T>::mutate(round_id, who, |locked| *locked = locked.saturating_add(amount));
+		Ok(())
+	}
+
+	/// Check that `who` owns a project whose team can still change, which is until the first vote on it,
+	/// and return the vote_hash of the project
+	fn open_team(round_id: u32, hash: T::Hash, who: &T::AccountId) -> Result<T::Hash, dispatch::DispatchError> {
+		ensure!(Projects::<T>::contains_key(&round_id, &hash), Error::<T>::ProjectNotExist);
+		ensure!(&Projects::<T>::get(round_id, hash).owner == who, Error::<T>::NotProjectOwner);
+		ensure!(Rounds::get(round_id).ongoing, Error::<T>::RoundHasEnded);
+		let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
+		// self votes are recognized by the team at the time of the vote
+		ensure!(ProjectVotes::<T>::iter_prefix(vote_hash).next().is_none(), Error::<T>::VotingStarted);
+		Ok(vote_hash)
+	}
+
+	/// The vote record of `who` on a project, its weight is fixed at the first vote by the self vote policy
+	/// and the identity level of `who`, as whether it is a discounted self vote, and kept until the round ends
+	fn voter_record(round_id: u32, hash: &T::Hash, vote_hash: &T::Hash, who: &T::AccountId) -> Result<VoteRecord, Error<T>> {
+		let is_team = &Projects::<T>::get(round_id, hash).owner == who || ProjectMembers::<T>::get(vote_hash, who);
+		let policy = SelfVotePolicies::get(round_id);
+		ensure!(!(is_team && policy == SelfVotePolicy::Forbid), Error::<T>::SelfVoteForbidden);
+		// a record is kept after all its votes are taken back, so a new vote can not change its weight
+		if ProjectVotes::<T>::contains_key(vote_hash, who) {
+			return Ok(ProjectVotes::<T>::get(vote_hash, who));
+		}
+		// a discounted self vote has no weight in the support area, later policy changes do not affect it
+		let discounted = is_team && policy == SelfVotePolicy::Discount;
+		let weight = if discounted { 0 } else { Self::identity_weight(round_id, who) };
+		Ok(VoteRecord { weight, discounted, ..Default::default() })
+	}
+
+	/// Vote for or `against` a project, each side costs the quadratic sum of the ballots of `who` on that side
+	fn do_vote(who: T::AccountId, round_id: u32, hash: T::Hash, ballot: u128, against: bool, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
+		let (mut round, newly_verified) = Self::voting_round(&who, round_id, against, proof)?;
+		let terms = Self::vote_terms(&who, round_id, &round, hash, ballot, against)?;
+		// pay first, update last, as it will ensure the balance is enough
+		Self::pay_votes(&who, round_id, &round, terms.amount, terms.cost, against)?;
+		Self::apply_vote(&who, round_id, &mut round, terms, newly_verified, against);
+		Rounds::insert(round_id, round);
+		Ok(())
+	}
+
+	/// Vote to several projects, all votes are checked before a single payment
+	fn do_vote_many(who: T::AccountId, round_id: u32, votes: Vec<(T::Hash, u128)>, proof: Option<Vec<T::Hash>>) -> dispatch::DispatchResult {
+		ensure!(!votes.is_empty() && votes.len() <= MAX_BATCH_VOTES, Error::<T>::InvalidBatch);
+		let (mut round, newly_verified) = Self::voting_round(&who, round_id, false, proof)?;
+		let mut batch: Vec<VoteTerms<T::Hash>> = Vec::with_capacity(votes.len());
+		for (hash, ballot) in votes {
+			// the cost of a vote depends on the previous ones of the project
+			ensure!(!batch.iter().any(|terms| terms.hash == hash), Error::<T>::InvalidBatch);
+			batch.push(Self::vote_terms(&who, round_id, &round, hash, ballot, false)?);
+		}
+		let amount = batch.iter().fold(0u128, |total, terms| total.saturating_add(terms.amount));
+		let cost = batch.iter().fold(0u128, |total, terms| total.saturating_add(terms.cost));
+		Self::pay_votes(&who, round_id, &round, amount, cost, false)?;
+		for (index, terms) in batch.into_iter().enumerate() {
+			Self::apply_vote(&who, round_id, &mut round, terms, newly_verified && index == 0, false);
+		}
+		Rounds::insert(round_id, round);
+		Ok(())
+	}
+
+	/// The vote points `budget` pays in a round, `budget` is capped by what `who` can spend
+	fn budget_points(who: &T::AccountId, round_id: u32, budget: u128) -> u128 {
+		let params = Rounds::get(round_id).params;
+		let free = Self::balance_to_u128(T::Assets::free_balance(params.vote_asset, who));
+		// a vote which overflows can not be paid
+		let vote = Self::cal_amount(&params, 1, false).unwrap_or(u128::max_value());
+		match params.vote_mode {
+			VoteMode::Credits => budget.min(VoiceCredits::<T>::get(round_id, who).unwrap_or(params.credits_per_voter)),
+			VoteMode::Lock => budget.min(free.saturating_sub(TotalLocked::<T>::get(who))) / vote,
+			VoteMode::Spend | VoteMode::Contribution => budget.min(free) / vote,
+		}
+	}
+
+	/// Split `points` between projects in proportion to their weights, given the ballots `who` already has on them.
+	/// The largest proportional split that fits is found by bisection, then each project can get one more ballot
+	/// by decreasing weight while the points allow it
+	fn allocate_points(who: &T::AccountId, round_id: u32, points: u128, weights: &[(T::Hash, u32)]) -> Vec<(T::Hash, u128)> {
+		// (hash, weight, ballots already voted)
+		let mut targets: Vec<(T::Hash, u128, u128)> = Vec::new();
+		for (hash, weight) in weights {
+			if *weight == 0 || !Projects::<T>::contains_key(round_id, hash) || targets.iter().any(|(h, _, _)| h == hash) {
+				continue;
+			}
+			let record = ProjectVotes::<T>::get(T::Hashing::hash_of(&(hash, &round_id)), who);
+			// votes against a project can not be mixed with votes for it
+			if record.against == 0 {
+				targets.push((*hash, *weight as u128, record.ballots));
+			}
+		}
+		let max_weight = targets.iter().map(|(_, weight, _)| *weight).max().unwrap_or(1);
+		// the project with the largest weight gets `top` ballots
+		let ballots_at = |top: u128| -> Vec<u128> {
+			targets.iter().map(|(_, weight, _)| top * weight / max_weight).collect()
+		};
+		// a cost too large to price can not be afforded
+		let cost_of = |ballots: &[u128]| -> u128 {
+			targets.iter().zip(ballots).fold(0u128, |total, ((_, _, voted), ballot)| {
+				total.saturating_add(Self::cal_cost(*voted, *ballot).unwrap_or(u128::MAX))
+			})
+		};
+		// `top` ballots cost at least top²/2
+		let (mut low, mut high) = (0u128, points.saturating_mul(2).integer_sqrt() + 1);
+		while low < high {
+			let middle = (low + high + 1) / 2;
+			if cost_of(&ballots_at(middle)) <= points {
+				low = middle;
+			} else {
+				high = middle - 1;
+			}
+		}
+		let mut ballots = ballots_at(low);
+		let mut spent = cost_of(&ballots);
+		let mut order: Vec<usize> = (0..targets.len()).collect();
+		order.sort_by(|a, b| targets[*b].1.cmp(&targets[*a].1));
+		for index in order {
+			let extra = Self::cal_cost(targets[index].2 + ballots[index], 1).unwrap_or(u128::MAX);
+			if spent.saturating_add(extra) <= points {
+				ballots[index] += 1;
+				spent += extra;
+			}
+		}
+		targets.into_iter().zip(ballots)
+			.filter(|(_, ballot)| *ballot > 0)
+			.map(|((hash, _, _), ballot)| (hash, ballot))
+			.collect()
+	}
+
+	/// Check that `who` can vote in a round, also returns whether `proof` newly verifies it in the allowlist
+	fn voting_round(who: &T::AccountId, round_id: u32, against: bool, proof: Option<Vec<T::Hash>>) -> Result<(Round, bool), dispatch::DispatchError> {
+		ensure!(T::VoterEligibility::is_eligible(who), Error::<T>::VoterNotEligible);
+		// check whether this round still ongoing
+		ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+		let round = Rounds::get(round_id);
+		ensure!(true == round.ongoing, Error::<T>::RoundHasEnded);
+		ensure!(round.params.vote_mode != VoteMode::Contribution, Error::<T>::WrongVoteMode);
+		ensure!(!against || round.params.negative_votes != NegativeVotes::Disabled, Error::<T>::NegativeVotesDisabled);
+		let newly_verified = Self::check_allowlist(round_id, who, proof)?;
+		Ok((round, newly_verified))
+	}
+
+	/// Check a vote of `who` on a project of `round` and compute what it costs
+	fn vote_terms(who: &T::AccountId, round_id: u32, round: &Round, hash: T::Hash, ballot: u128, against: bool) -> Result<VoteTerms<T::Hash>, dispatch::DispatchError> {
+		ensure!(Projects::<T>::contains_key(&round_id, &hash), Error::<T>::ProjectNotExist);
+		ensure!(ballot > 0, Error::<T>::InvalidBallot);
+		// need to calculate hash of project hash and round_id combination here to avoid conflicts of projects in different rounds
+		let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
+		let record = Self::voter_record(round_id, &hash, &vote_hash, who)?;
+		// an account can only be on one side of a project
+		ensure!(if against { record.ballots == 0 } else { record.against == 0 }, Error::<T>::ConflictingVote);
+		let voted = if against { record.against } else { record.ballots };
+		let cost = Self::cal_cost(voted, ballot)?;
+		let amount = Self::cal_amount(&round.params, cost, false)?;
+		let spending = round.params.vote_mode == VoteMode::Spend;
+		let burning = against && round.params.negative_votes == NegativeVotes::Burn;
+		// nothing is paid for locked votes or voice credits, burned votes are not taxed
+		let fee = if spending && !burning { Self::cal_amount(&round.params, cost, true)? } else { 0 };
+		Ok(VoteTerms { hash, vote_hash, record, voted, ballot, cost, amount, fee })
+	}
+
+	/// Pay `amount` for votes by transfer or lock, or their `cost` in voice credits
+	fn pay_votes(who: &T::AccountId, round_id: u32, round: &Round, amount: u128, cost: u128, against: bool) -> dispatch::DispatchResult {
+		let burning = against && round.params.negative_votes == NegativeVotes::Burn;
+		match round.params.vote_mode {
+			// the imbalance is dropped, which burns it
+			VoteMode::Spend if burning => drop(
+				T::Currency::withdraw(who, Self::u128_to_balance(amount), WithdrawReasons::from(WithdrawReason::Transfer), KeepAlive)?
+			),
+			VoteMode::Spend | VoteMode::Contribution => T::Assets::transfer(round.params.vote_asset, who, &Self::account_id(), Self::u128_to_balance(amount))?,
+			VoteMode::Lock => Self::lock_votes(round_id, who, amount)?,
+			VoteMode::Credits => Self::spend_credits(round_id, &round.params, who, cost)?,
+		}
+		Ok(())
+	}
+
+	/// Record a paid vote in the project and `round`
+	fn apply_vote(who: &T::AccountId, round_id: u32, round: &mut Round, terms: VoteTerms<T::Hash>, newly_verified: bool, against: bool) {
+		let VoteTerms { hash, vote_hash, mut record, voted, ballot, cost, amount, fee } = terms;
+		let discounted = record.discounted;
+		let spending = round.params.vote_mode == VoteMode::Spend;
+		let burning = against && round.params.negative_votes == NegativeVotes::Burn;
+		let weight = record.weight as u128;
+		// update the project and corresponding round
+		if newly_verified {
+			VerifiedVoters::<T>::insert(round_id, who, true);
+			Self::deposit_event(RawEvent::VoterVerified(round_id, who.clone()));
+		}
+		if against {
+			record.against = ballot+voted;
 		} else {
-			uov.checked_mul(nup).unwrap().checked_mul(amount).unwrap()
+			record.ballots = ballot+voted;
+		}
+		ProjectVotes::<T>::insert(vote_hash, who, record);
+		let mut poj = Projects::<T>::get(round_id, hash);
+		if against {
+			Self::add_dissent(round_id, round, &mut poj, ballot, weight);
+			if spending && !burning {
+				round.support_pool = (amount - fee).checked_add(round.support_pool).unwrap();
+				round.votes_to_pool = (amount - fee).checked_add(round.votes_to_pool).unwrap();
+			}
+		} else {
+			let support_area = Self::add_support(round_id, round, &mut poj, voted, ballot, weight, discounted);
+			if spending {
+				poj.grants += amount - fee;
+			}
+			debug::info!("Total votes: {:?}, Current votes: {:?}, Support Area: {:?},Est cost: {:?}",
+			poj.total_votes, voted, support_area, cost);
+		}
+		round.total_tax = fee.checked_add(round.total_tax).unwrap();
+		round.vote_tax = fee.checked_add(round.vote_tax).unwrap();
+		Projects::<T>::insert(round_id, hash, poj);
+		if against {
+			Self::deposit_event(RawEvent::VoteAgainstSucceed(hash, who.clone(), ballot));
+		} else {
+			Self::deposit_event(RawEvent::VoteSucceed(hash, who.clone(), ballot));
 		}
 	}
 
+	/// Take back `ballot` of the votes of `who` for a project, refunding the cost difference minus the fee
+	fn do_unvote(who: T::AccountId, round_id: u32, hash: T::Hash, ballot: u128) -> dispatch::DispatchResult {
+		ensure!(Projects::<T>::contains_key(&round_id, &hash), Error::<T>::ProjectNotExist);
+		ensure!(ballot > 0, Error::<T>::InvalidBallot);
+		ensure!(Rounds::contains_key(&round_id), Error::<T>::RoundNotExist);
+		let mut round = Rounds::get(round_id);
+		ensure!(round.ongoing, Error::<T>::RoundHasEnded);
+		ensure!(round.params.vote_mode != VoteMode::Contribution, Error::<T>::WrongVoteMode);
+		let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
+		// the policies only apply to new votes, the record keeps its weight and discount
+		let mut record = ProjectVotes::<T>::get(&vote_hash, &who);
+		ensure!(record.ballots >= ballot, Error::<T>::InsufficientBallots);
+		let voted = record.ballots;
+		let weight = record.weight as u128;
+		// the cost of the last `ballot` votes
+		let cost = Self::cal_cost(voted - ballot, ballot)?;
+		let amount = Self::cal_amount(&round.params, cost, false)?;
+		let spending = round.params.vote_mode == VoteMode::Spend;
+		// the fee is kept
+		let fee = if spending { Self::cal_amount(&round.params, cost, true)? } else { 0 };
+		let refund = amount - fee;
+		match round.params.vote_mode {
+			VoteMode::Spend => T::Assets::transfer(round.params.vote_asset, &Self::account_id(), &who, Self::u128_to_balance(refund))?,
+			// contributions can not be taken back, this is refused above
+			VoteMode::Contribution => {},
+			VoteMode::Lock => {
+				Self::reduce_lock(&who, refund);
+				LockedVotes::<T>::mutate(round_id, &who, |locked| *locked = locked.saturating_sub(refund));
+			},
+			VoteMode::Credits => VoiceCredits::<T>::mutate(round_id, &who, |credits| *credits = credits.map(|c| c.saturating_add(cost))),
+		}
+
+		record.ballots = voted - ballot;
+		ProjectVotes::<T>::insert(vote_hash, &who, &record);
+		let mut poj = Projects::<T>::get(round_id, hash);
+		Self::remove_support(round_id, &mut round, &mut poj, voted, ballot, weight, record.discounted);
+		if spending {
+			poj.grants -= refund;
+		}
+		Projects::<T>::insert(round_id, hash, poj);
+		Rounds::insert(round_id, round);
+		Self::deposit_event(RawEvent::VoteWithdrawn(hash, who, ballot, refund));
+		Ok(())
+	}
+
+	/// The support area of a project once reduced by the votes against it, floored at zero.
+	/// Votes against take away their cross term with the votes for, as in (A - B)²
+	pub fn effective_area(poj: &ProjectOf<T>) -> u128 {
+		poj.support_area.saturating_sub(poj.weighted_votes.saturating_mul(poj.weighted_against))
+	}
+
+	/// Add `ballot` to the `voted` ballots of a voter with `weight` on a project, and its support area to the round,
+	/// or to the track of the project if it has one. Returns the support area added
+	fn add_support(round_id: u32, round: &mut Round, poj: &mut ProjectOf<T>, voted: u128, ballot: u128, weight: u128, discounted: bool) -> u128 {
+		let effective = Self::effective_area(poj);
+		// the area between this voter and all the others, each side weighted by identity
+		let weighted_ballot = ballot.checked_mul(weight).unwrap();
+		let support_area = weighted_ballot.checked_mul(poj.weighted_votes - voted * weight).unwrap();
+		poj.support_area = support_area.checked_add(poj.support_area).unwrap();
+		poj.total_votes += ballot;
+		poj.weighted_votes += weighted_ballot;
+		if discounted {
+			poj.discounted_votes += ballot;
+		}
+		Self::update_area(round_id, round, poj.track, effective, Self::effective_area(poj));
+		support_area
+	}
+
+	/// Remove `ballot` from the `voted` ballots of a voter with `weight` on a project, which takes away
+	/// exactly the area it added with the other voters
+	fn remove_support(round_id: u32, round: &mut Round, poj: &mut ProjectOf<T>, voted: u128, ballot: u128, weight: u128, discounted: bool) {
+		let effective = Self::effective_area(poj);
+		let weighted_ballot = ballot.checked_mul(weight).unwrap();
+		let support_area = weighted_ballot.checked_mul(poj.weighted_votes - voted * weight).unwrap();
+		poj.support_area -= support_area;
+		poj.total_votes -= ballot;
+		poj.weighted_votes -= weighted_ballot;
+		if discounted {
+			poj.discounted_votes -= ballot;
+		}
+		Self::update_area(round_id, round, poj.track, effective, Self::effective_area(poj));
+	}
+
+	/// Add `ballot` against a project from a voter with `weight`
+	fn add_dissent(round_id: u32, round: &mut Round, poj: &mut ProjectOf<T>, ballot: u128, weight: u128) {
+		let effective = Self::effective_area(poj);
+		poj.votes_against += ballot;
+		poj.weighted_against += ballot.checked_mul(weight).unwrap();
+		Self::update_area(round_id, round, poj.track, effective, Self::effective_area(poj));
+	}
+
+	/// Replace the `before` effective area of a project by `after` in the total of its round
+	fn update_area(round_id: u32, round: &mut Round, track: Option<u32>, before: u128, after: u128) {
+		// the area of a project in a track only counts in its track
+		if let Some(t) = track {
+			Tracks::mutate(round_id, |tracks| {
+				let track = &mut tracks[t as usize];
+				track.total_support_area = after.checked_add(track.total_support_area).unwrap() - before;
+			});
+		} else {
+			round.total_support_area = after.checked_add(round.total_support_area).unwrap() - before;
+		}
+	}
+
+	/// Record a contribution already transferred to the pallet account, the square root of the total contributed by
+	/// `who` in units of vote replaces its ballots on the project
+	fn add_contribution(round_id: u32, hash: T::Hash, vote_hash: T::Hash, who: &T::AccountId, mut record: VoteRecord, amount: u128) -> Result<(), Error<T>> {
+		let mut round = Rounds::get(round_id);
+		let mut poj = Projects::<T>::get(round_id, hash);
+		let unit = round.params.unit_of_vote;
+		let previous = Contributions::<T>::get(vote_hash, who);
+		let contributed = previous.checked_add(amount).ok_or(Error::<T>::AmountOverflow)?;
+		// the fee is taken on the total, so that the rounding of small contributions does not add up
+		let fee = Self::contribution_fee(&round.params, contributed)? - Self::contribution_fee(&round.params, previous)?;
+		let root = (contributed / unit).integer_sqrt();
+		let voted = record.ballots;
+		Self::add_support(round_id, &mut round, &mut poj, voted, root - voted, record.weight as u128, record.discounted);
+		poj.grants += amount - fee;
+		round.total_tax = fee.checked_add(round.total_tax).unwrap();
+		round.vote_tax = fee.checked_add(round.vote_tax).unwrap();
+		record.ballots = root;
+		ProjectVotes::<T>::insert(vote_hash, who, record);
+		Contributions::<T>::insert(vote_hash, who, contributed);
+		Projects::<T>::insert(round_id, hash, poj);
+		Rounds::insert(round_id, round);
+		Ok(())
+	}
+
+	/// The fee of a total contribution, which has the same ratio as for votes
+	fn contribution_fee(params: &RoundParams, contributed: u128) -> Result<u128, Error<T>> {
+		multiply_by_rational(contributed, params.fee_ratio_per_vote, params.number_of_unit_per_vote)
+			.map_err(|_| Error::<T>::AmountOverflow)
+	}
+
+	/// The total cost of several votes from `who` in a round, as `vote_cost` of each
+	pub fn batch_vote_cost(who: T::AccountId, round_id: u32, votes: Vec<(T::Hash, u32)>) -> u32 {
+		votes.into_iter().fold(0u32, |total, (hash, ballot)| total.saturating_add(Self::vote_cost(who.clone(), round_id, hash, ballot)))
+	}
+
+	/// The ballots `vote_with_budget` would vote with `budget` in units of vote, or in voice credits
+	pub fn allocate_budget(who: T::AccountId, round_id: u32, budget: u32, weights: Vec<(T::Hash, u32)>) -> Vec<(T::Hash, u32)> {
+		if !Rounds::contains_key(round_id) {
+			return Vec::new();
+		}
+		let params = Rounds::get(round_id).params;
+		let budget = if params.vote_mode == VoteMode::Credits { budget as u128 } else { (budget as u128).saturating_mul(params.unit_of_vote) };
+		let points = Self::budget_points(&who, round_id, budget);
+		Self::allocate_points(&who, round_id, points, &weights).into_iter()
+			.map(|(hash, ballot)| (hash, TryInto::<u32>::try_into(ballot).ok().unwrap()))
+			.collect()
+	}
+
+	/// The matching of each project of a round from all its pools
+	fn matching_totals(round_id: u32) -> Vec<(T::Hash, u128)> {
+		Self::matching(round_id, &Rounds::get(round_id)).into_iter()
+			.map(|(hash, _, sources)| (hash, sources.iter().fold(0u128, |total, (_, matching)| total.saturating_add(*matching))))
+			.collect()
+	}
+
+	/// Simulate a vote of `who` on the current state, a negative `ballot` votes against the project.
+	/// `proof` is needed as for a vote until `who` is verified, a vote which would fail is not eligible and has no impact
+	pub fn estimate_impact(who: T::AccountId, round_id: u32, hash: T::Hash, ballot: i32, proof: Option<Vec<T::Hash>>) -> VoteImpact<T::Hash> {
+		let against = ballot < 0;
+		// the same checks as a vote: eligibility, the round and its allowlist
+		let round = match Self::voting_round(&who, round_id, against, proof) {
+			Ok((round, _)) => round,
+			Err(_) => return VoteImpact::default(),
+		};
+		let unit = round.params.unit_of_vote.max(1);
+		let ballot = (ballot as i64).abs() as u128;
+		let before = Self::matching_totals(round_id);
+		// the vote is applied then rolled back
+		let after = with_transaction(|| {
+			let mut round = round;
+			let after = Self::vote_terms(&who, round_id, &round, hash, ballot, against).map(|terms| {
+				Self::apply_vote(&who, round_id, &mut round, terms, false, against);
+				Rounds::insert(round_id, &round);
+				(Self::matching_totals(round_id), Self::effective_area(&Projects::<T>::get(round_id, hash)))
+			});
+			TransactionOutcome::Rollback(after)
+		});
+		let (after, support_area) = match after {
+			Ok(after) => after,
+			Err(_) => return VoteImpact::default(),
+		};
+		let matching = after.iter().find(|(h, _)| h == &hash).map_or(0, |(_, matching)| *matching);
+		let changes = after.iter().map(|(h, matching)| {
+			let previous = before.iter().find(|(b, _)| b == h).map_or(0, |(_, matching)| *matching);
+			(*h, ((*matching as i128 - previous as i128) / unit as i128) as i64)
+		}).collect();
+		VoteImpact {
+			eligible: true,
+			support_area: TryInto::<u64>::try_into(support_area).unwrap_or(u64::max_value()),
+			matching: TryInto::<u32>::try_into(matching / unit).ok().unwrap(),
+			changes,
+		}
+	}
+
+	/// The matching of a project from all its pools, now and after `who` contributes `amount` units of vote to it
+	pub fn contribution_impact(who: T::AccountId, round_id: u32, hash: T::Hash, amount: u32) -> (u32, u32) {
+		let round = Rounds::get(round_id);
+		if !round.ongoing || round.params.vote_mode != VoteMode::Contribution || !Projects::<T>::contains_key(round_id, hash) {
+			return (0, 0);
+		}
+		let unit = round.params.unit_of_vote.max(1);
+		let total = |round_id: u32| -> u32 {
+			let matching = Self::matching_totals(round_id).into_iter()
+				.find(|(h, _)| h == &hash)
+				.map_or(0, |(_, matching)| matching);
+			TryInto::<u32>::try_into(matching / unit).ok().unwrap()
+		};
+		let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
+		let before = total(round_id);
+		let after = match Self::voter_record(round_id, &hash, &vote_hash, &who) {
+			// the contribution is recorded then rolled back
+			Ok(record) => with_transaction(|| {
+				let after = Self::add_contribution(round_id, hash, vote_hash, &who, record, (amount as u128).saturating_mul(unit))
+					.map_or(before, |_| total(round_id));
+				TransactionOutcome::Rollback(after)
+			}),
+			Err(_) => before,
+		};
+		(before, after)
+	}
+
+	/// What `who` paid for its votes to a project and did not take back, minus the fee
+	fn vote_payment(params: &RoundParams, vote_hash: &T::Hash, who: &T::AccountId) -> Result<u128, Error<T>> {
+		let record = ProjectVotes::<T>::get(vote_hash, who);
+		let paid = |ballots: u128| -> Result<u128, Error<T>> {
+			let cost = Self::cal_cost(0, ballots)?;
+			Ok(Self::cal_amount(params, cost, false)? - Self::cal_amount(params, cost, true)?)
+		};
+		Ok(match params.vote_mode {
+			// burned votes against can not be refunded
+			VoteMode::Spend if params.negative_votes == NegativeVotes::ToPool => paid(record.ballots)?.saturating_add(paid(record.against)?),
+			VoteMode::Spend => paid(record.ballots)?,
+			VoteMode::Contribution => {
+				let contributed = Contributions::<T>::get(vote_hash, who);
+				contributed - Self::contribution_fee(params, contributed)?
+			},
+			// locks are released by `unlock`, voice credits are not funds
+			VoteMode::Lock | VoteMode::Credits => 0,
+		})
+	}
+
+	/// Pay `cost` in voice credits, an account gets `credits_per_voter` at its first vote unless it was allocated some
+	fn spend_credits(round_id: u32, params: &RoundParams, who: &T::AccountId, cost: u128) -> dispatch::DispatchResult {
+		let credits = VoiceCredits::<T>::get(round_id, who).unwrap_or(params.credits_per_voter);
+		ensure!(credits >= cost, Error::<T>::InsufficientCredits);
+		VoiceCredits::<T>::insert(round_id, who, credits - cost);
+		Ok(())
+	}
+
+	/// Unlock `amount` of the votes locked by `who` in all rounds
+	fn reduce_lock(who: &T::AccountId, amount: u128) {
+		let total = TotalLocked::<T>::get(who).saturating_sub(amount);
+		if total == 0 {
+			T::Currency::remove_lock(T::VoteLockId::get(), who);
+			TotalLocked::<T>::remove(who);
+		} else {
+			T::Currency::set_lock(T::VoteLockId::get(), who, Self::u128_to_balance(total), WithdrawReasons::all());
+			TotalLocked::<T>::insert(who, total);
+		}
+	}
+
+	/// The amount of `amount` votes in the vote asset, or its fee, an overflow is an error
+	pub fn cal_amount(params: &RoundParams, amount: u128, is_fee: bool) -> Result<u128, Error<T>> {
+		let uov = params.unit_of_vote;
+		let nup = params.number_of_unit_per_vote;
+		let frpv = params.fee_ratio_per_vote;
+		let ratio = if is_fee { frpv } else { nup };
+		uov.checked_mul(ratio)
+			.and_then(|unit| unit.checked_mul(amount))
+			.ok_or(Error::<T>::AmountOverflow)
+	}
+
 	pub fn u128_to_balance(cost: u128) -> BalanceOf<T> {
 		TryInto::<BalanceOf::<T>>::try_into(cost).ok().unwrap()
 	}
@@ -344,30 +1909,268 @@ impl<T: Config> Module<T> {
 	}
 
 	// TODO: There is a bug for serde_json, can not use u128 https://github.com/paritytech/substrate/issues/4641
+	/// Return 0 if `who` is not eligible to vote
 	pub fn vote_cost(who: T::AccountId, round_id:u32, hash: T::Hash, ballot: u32) -> u32 {
+		Self::ballot_cost(who, round_id, hash, ballot, false)
+	}
+
+	/// The cost of votes against a project, 0 if the round does not accept them or `who` is not eligible to vote
+	pub fn vote_against_cost(who: T::AccountId, round_id: u32, hash: T::Hash, ballot: u32) -> u32 {
+		if Rounds::get(round_id).params.negative_votes == NegativeVotes::Disabled {
+			return 0;
+		}
+		Self::ballot_cost(who, round_id, hash, ballot, true)
+	}
+
+	/// The cost of `ballot` more votes of `who` on one side of a project
+	fn ballot_cost(who: T::AccountId, round_id: u32, hash: T::Hash, ballot: u32, against: bool) -> u32 {
+		if !T::VoterEligibility::is_eligible(&who) {
+			return 0;
+		}
 		// need to calculate hash of project hash and round_id combination here to avoid conflicts of projects in different rounds
 		let vote_hash = T::Hashing::hash_of(&(&hash, &round_id));
-		let voted = ProjectVotes::<T>::get(vote_hash, &who);
-		TryInto::<u32>::try_into(Self::cal_cost(voted, ballot.into())).ok().unwrap()
+		let record = ProjectVotes::<T>::get(vote_hash, &who);
+		let voted = if against { record.against } else { record.ballots };
+		// a cost that does not fit is reported as the largest one
+		Self::cal_cost(voted, ballot.into()).ok()
+			.and_then(|cost| TryInto::<u32>::try_into(cost).ok())
+			.unwrap_or(u32::MAX)
 	}
 
-	// TODO, using struct is a little complicate, use tuple instead
-	// (project_id, total_votes, grants, support_grants)
-	pub fn projects_per_round(round_id:u32) -> Vec<(T::Hash, u32, u32, u32)> {
-		let mut projects  = vec![];
-		let round = Rounds::get(round_id);
-		let area = round.total_support_area;
-		let pool = round.support_pool;
-		for (hash, project) in Projects::<T>::iter_prefix(round_id) {
-			let mut sg = 0;
+	/// What the pallet account holds for a round in (matching asset, vote asset): the pools and the tax of
+	/// donations, the unpaid grants and the tax of votes while it is ongoing, the unallocated funds and
+	/// the refunds of voters once it has ended
+	fn round_obligations(round_id: u32, round: &Round) -> (u128, u128) {
+		if !round.ongoing {
+			return (round.unallocated, round.unallocated_votes.saturating_add(round.voter_refunds));
+		}
+		let pools = DirectedPools::<T>::get(round_id).iter()
+			.fold(round.support_pool, |total, pool| total.saturating_add(pool.amount));
+		let pools = Tracks::get(round_id).iter()
+			.fold(pools, |total, track| total.saturating_add(track.support_pool));
+		let votes = Projects::<T>::iter_prefix_values(round_id)
+			.fold(round.vote_tax, |total, project| total.saturating_add(project.grants.saturating_sub(project.withdrew)));
+		(pools.saturating_add(round.total_tax.saturating_sub(round.vote_tax)), votes)
+	}
+
+	/// Each project of a round with its matching from its main pool (`None`) and from each directed pool.
+	/// The main pool is the pool of its track, or of the round for projects without track,
+	/// a directed pool is split by the support area of the projects it targets.
+	/// With voice credits, the weighted votes of a project (minus the votes against it) replace its support area.
+	pub fn matching(round_id: u32, round: &Round) -> Vec<(T::Hash, ProjectOf<T>, Vec<(Option<u32>, u128)>)> {
+		let pools = DirectedPools::<T>::get(round_id);
+		let projects: Vec<_> = Projects::<T>::iter_prefix(round_id).map(|(hash, project)| {
+			let tags = ProjectTags::<T>::get(round_id, &hash);
+			(hash, project, tags)
+		}).collect();
+		let credits = round.params.vote_mode == VoteMode::Credits;
+		let score = |project: &ProjectOf<T>| if credits {
+			project.weighted_votes.saturating_sub(project.weighted_against)
+		} else {
+			Self::effective_area(project)
+		};
+		let areas: Vec<u128> = pools.iter().map(|pool| {
+			projects.iter()
+				.filter(|(hash, project, tags)| pool.targets_project(hash, project.track, tags))
+				.fold(0u128, |total, (_, project, _)| total.saturating_add(score(project)))
+		}).collect();
+		let tracks = Tracks::get(round_id);
+		// the area of each track, and of the round at the end
+		let main_areas: Vec<u128> = (0..=tracks.len()).map(|index| {
+			let track = if index < tracks.len() { Some(index as u32) } else { None };
+			if credits {
+				projects.iter()
+					.filter(|(_, project, _)| project.track == track)
+					.fold(0u128, |total, (_, project, _)| total.saturating_add(score(project)))
+			} else {
+				track.map_or(round.total_support_area, |t| tracks[t as usize].total_support_area)
+			}
+		}).collect();
+		projects.into_iter().map(|(hash, project, tags)| {
+			let mut sources = Vec::new();
+			let (pool, area) = match project.track {
+				Some(t) => (tracks[t as usize].support_pool, main_areas[t as usize]),
+				None => (round.support_pool, main_areas[tracks.len()]),
+			};
 			if area > 0 {
-				sg = project.support_area.checked_mul(pool/area).unwrap()
+				sources.push((None, score(&project).checked_mul(pool/area).unwrap()));
+			}
+			for (index, pool) in pools.iter().enumerate() {
+				if areas[index] > 0 && pool.targets_project(&hash, project.track, &tags) {
+					sources.push((Some(index as u32), score(&project).checked_mul(pool.amount/areas[index]).unwrap()));
+				}
 			}
+			(hash, project, sources)
+		}).collect()
+	}
+
+	/// The matching of a project by source pool in UnitOfVote
+	pub fn project_matching(round_id: u32, hash: T::Hash) -> Vec<PoolMatching> {
+		let round = Rounds::get(round_id);
+		let unit = round.params.unit_of_vote.max(1);
+		Self::matching(round_id, &round).into_iter()
+			.find(|(h, _, _)| h == &hash)
+			.map(|(_, _, sources)| sources.into_iter().map(|(pool, matching)| PoolMatching {
+				pool,
+				matching: TryInto::<u32>::try_into(matching / unit).ok().unwrap(),
+				asset: round.params.matching_asset,
+			}).collect())
+			.unwrap_or_default()
+	}
+
+	/// The sum of the obligations of all rounds in `asset`, the free balance of the pallet account
+	/// should equal to this besides its existential deposit
+	pub fn outstanding_obligations(asset: AssetId) -> u128 {
+		Rounds::iter().fold(0u128, |total, (round_id, round)| {
+			let (matching, votes) = Self::round_obligations(round_id, &round);
+			let total = if round.params.matching_asset == asset { total.saturating_add(matching) } else { total };
+			if round.params.vote_asset == asset { total.saturating_add(votes) } else { total }
+		})
+	}
+
+	/// Move the tax to `FeeDestination`, or to `TreasuryAccount` if it is not in the native currency
+	fn pay_tax(asset: AssetId, amount: u128) -> bool {
+		if amount == 0 {
+			return true;
+		}
+		match asset {
+			AssetId::Native => T::Currency::withdraw(
+				&Self::account_id(),
+				Self::u128_to_balance(amount),
+				WithdrawReasons::from(WithdrawReason::Transfer),
+				KeepAlive
+			).map(T::FeeDestination::on_unbalanced).is_ok(),
+			AssetId::Token(_) => Self::pay_out(asset, &T::TreasuryAccount::get(), amount),
+		}
+	}
+
+	/// Transfer from the pallet account, return whether it succeeded
+	fn pay_out(asset: AssetId, to: &T::AccountId, amount: u128) -> bool {
+		amount == 0 || T::Assets::transfer(asset, &Self::account_id(), to, Self::u128_to_balance(amount)).is_ok()
+	}
+
+	/// Move the unallocated funds of `round` to the pool of the ongoing round `successor`,
+	/// in each asset of `round` which is the matching asset of `successor`
+	fn roll_over(round_id: u32, round: &mut Round, successor: u32) {
+		let asset = Rounds::get(successor).params.matching_asset;
+		let mut amount = 0u128;
+		if asset == round.params.matching_asset {
+			amount = amount.saturating_add(round.unallocated);
+			round.unallocated = 0;
+		}
+		if asset == round.params.vote_asset {
+			amount = amount.saturating_add(round.unallocated_votes);
+			round.unallocated_votes = 0;
+		}
+		Rounds::mutate(successor, |rnd| {
+			rnd.pre_tax_support_pool = rnd.pre_tax_support_pool.saturating_add(amount);
+			rnd.support_pool = rnd.support_pool.saturating_add(amount);
+		});
+		Self::deposit_event(RawEvent::RolledOver(round_id, successor, amount));
+	}
+
+	/// The global parameters for rpc, values which do not fit are saturated
+	pub fn parameters_info() -> ParametersInfo {
+		let parameters = Self::parameters();
+		let round = parameters.round;
+		let narrow = |value: u128| TryInto::<u32>::try_into(value).unwrap_or(u32::max_value());
+		let round_deposit = Self::balance_to_u128(parameters.round_deposit) / round.unit_of_vote.max(1);
+		ParametersInfo {
+			unit_of_vote: TryInto::<u64>::try_into(round.unit_of_vote).unwrap_or(u64::max_value()),
+			number_of_unit_per_vote: narrow(round.number_of_unit_per_vote),
+			fee_ratio_per_vote: narrow(round.fee_ratio_per_vote),
+			name_min_length: round.name_min_length,
+			name_max_length: round.name_max_length,
+			vote_asset: round.vote_asset,
+			matching_asset: round.matching_asset,
+			vote_mode: round.vote_mode,
+			credits_per_voter: narrow(round.credits_per_voter),
+			negative_votes: round.negative_votes,
+			round_deposit: narrow(round_deposit),
+		}
+	}
+
+	/// The tax of a round by asset in UnitOfVote, the tax of donations in the matching asset then the tax of votes
+	/// in the vote asset, a single entry if they are the same asset
+	pub fn round_fees(round_id: u32) -> Vec<RoundFee> {
+		let round = Rounds::get(round_id);
+		let unit = round.params.unit_of_vote.max(1);
+		let narrow = |value: u128| TryInto::<u32>::try_into(value / unit).unwrap_or(u32::max_value());
+		let mut fees = vec![(
+			round.params.matching_asset,
+			round.total_tax.saturating_sub(round.vote_tax),
+			round.tax_paid.saturating_sub(round.vote_tax_paid),
+		)];
+		if round.params.vote_asset == round.params.matching_asset {
+			fees[0].1 = round.total_tax;
+			fees[0].2 = round.tax_paid;
+		} else {
+			fees.push((round.params.vote_asset, round.vote_tax, round.vote_tax_paid));
+		}
+		fees.into_iter()
+			.map(|(asset, total_tax, tax_paid)| RoundFee { asset, total_tax: narrow(total_tax), tax_paid: narrow(tax_paid) })
+			.collect()
+	}
+
+	pub fn projects_per_round(round_id:u32) -> Vec<RankingProject<T::Hash>> {
+		let mut projects  = vec![];
+		let round = Rounds::get(round_id);
+		let unit = round.params.unit_of_vote;
+		for (hash, project, sources) in Self::matching(round_id, &round) {
+			let sg = sources.iter().fold(0u128, |total, (_, matching)| total.saturating_add(*matching));
 			let total_votes = TryInto::<u32>::try_into(project.total_votes).ok().unwrap();
-			let grants = TryInto::<u32>::try_into(project.grants.checked_div(T::UnitOfVote::get()).unwrap()).ok().unwrap();
-			let support_grants = TryInto::<u32>::try_into(sg.checked_div(T::UnitOfVote::get()).unwrap()).ok().unwrap();
-			projects.push((hash, total_votes, grants, support_grants))
+			// grants from votes only, the matching is in support_grants
+			let grants = TryInto::<u32>::try_into((project.grants - project.matching).checked_div(unit).unwrap()).ok().unwrap();
+			let support_grants = TryInto::<u32>::try_into(sg.checked_div(unit).unwrap()).ok().unwrap();
+			let discounted_votes = TryInto::<u32>::try_into(project.discounted_votes).ok().unwrap();
+			let votes_against = TryInto::<u32>::try_into(project.votes_against).ok().unwrap();
+			projects.push(RankingProject {
+				project_id: hash,
+				total_votes,
+				grants,
+				support_grants,
+				discounted_votes,
+				votes_against,
+				track: project.track,
+				vote_asset: round.params.vote_asset,
+				matching_asset: round.params.matching_asset,
+			})
 		}
 		projects
 	}
 }
+
+/// Record the creation block of every new account, so that voters can be filtered by account age
+impl<T: Config> OnNewAccount<T::AccountId> for Module<T> {
+	fn on_new_account(who: &T::AccountId) {
+		AccountBirth::<T>::insert(who, <frame_system::Module<T>>::block_number());
+	}
+}
+
+impl<T: Config> OnKilledAccount<T::AccountId> for Module<T> {
+	fn on_killed_account(who: &T::AccountId) {
+		AccountBirth::<T>::remove(who);
+	}
+}
+
+/// Credit the pool of the designated round, so that the runtime can keep funding it with a share of transaction fees.
+/// The imbalance is burned if no ongoing round is designated.
+impl<T: Config> OnUnbalanced<NegativeImbalanceOf<T>> for Module<T> {
+	fn on_nonzero_unbalanced(amount: NegativeImbalanceOf<T>) {
+		let designated = |id: &u32| {
+			let round = Rounds::get(*id);
+			round.ongoing && round.params.matching_asset == AssetId::Native
+		};
+		let round_id = match DesignatedRound::get().filter(designated) {
+			Some(round_id) => round_id,
+			None => return,
+		};
+		let amount_number = Self::balance_to_u128(amount.peek());
+		T::Currency::resolve_creating(&Self::account_id(), amount);
+		Rounds::mutate(round_id, |rnd| {
+			rnd.pre_tax_support_pool = rnd.pre_tax_support_pool.saturating_add(amount_number);
+			rnd.support_pool = rnd.support_pool.saturating_add(amount_number);
+		});
+		Self::deposit_event(RawEvent::PoolFunded(round_id, amount_number));
+	}
+}
@@ -0,0 +1,61 @@
+//! A minimal merkle tree used for voter allowlists.
+//! Each pair of nodes is hashed in sorted order, so a proof is just the list of sibling hashes
+//! and the same functions can be used by the runtime to verify and by the node to build proofs.
+
+use frame_support::codec::Encode;
+use sp_runtime::traits::Hash;
+use sp_std::vec::Vec;
+
+/// The leaf of an account
+pub fn leaf<H: Hash, AccountId: Encode>(who: &AccountId) -> H::Output {
+	H::hash_of(who)
+}
+
+fn hash_pair<H: Hash>(a: &H::Output, b: &H::Output) -> H::Output {
+	if a.as_ref() <= b.as_ref() {
+		H::hash_of(&(a, b))
+	} else {
+		H::hash_of(&(b, a))
+	}
+}
+
+// An odd node at the end of a level is promoted to the next level as it is
+fn next_level<H: Hash>(level: &[H::Output]) -> Vec<H::Output> {
+	level.chunks(2).map(|pair| {
+		if pair.len() == 2 { hash_pair::<H>(&pair[0], &pair[1]) } else { pair[0] }
+	}).collect()
+}
+
+/// Calculate the root of the given leaves, an empty list has the default hash as root
+pub fn root<H: Hash>(leaves: Vec<H::Output>) -> H::Output {
+	let mut level = leaves;
+	if level.is_empty() {
+		return Default::default();
+	}
+	while level.len() > 1 {
+		level = next_level::<H>(&level);
+	}
+	level[0]
+}
+
+/// Build the proof of the leaf at `index`
+pub fn proof<H: Hash>(leaves: Vec<H::Output>, index: usize) -> Vec<H::Output> {
+	let mut proof = Vec::new();
+	let mut level = leaves;
+	let mut index = index;
+	while level.len() > 1 {
+		let sibling = index ^ 1;
+		if sibling < level.len() {
+			proof.push(level[sibling]);
+		}
+		level = next_level::<H>(&level);
+		index /= 2;
+	}
+	proof
+}
+
+/// Check that `leaf` is included in the tree with `root`
+pub fn verify<H: Hash>(root: &H::Output, leaf: H::Output, proof: &[H::Output]) -> bool {
+	let computed = proof.iter().fold(leaf, |node, sibling| hash_pair::<H>(&node, sibling));
+	&computed == root
+}
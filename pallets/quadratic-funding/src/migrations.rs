@@ -0,0 +1,79 @@
+//! Storage migrations, run by `on_runtime_upgrade` of this pallet.
+
+use frame_support::{
+	traits::Get, weights::Weight, codec::Decode,
+	storage::{IterableStorageMap, IterableStorageDoubleMap, StorageValue},
+};
+use sp_std::vec::Vec;
+use crate::{Config, Module, Round, Project, VoteRecord, Releases, Rounds, Projects, ProjectVotes, NextRoundId, StorageVersion};
+
+/// A round before `Releases::V2_0_0`
+#[derive(Decode)]
+struct OldRound {
+	ongoing: bool,
+	support_pool: u128,
+	pre_tax_support_pool: u128,
+	total_support_area: u128,
+	total_tax: u128,
+}
+
+/// A project before `Releases::V2_0_0`
+#[derive(Decode)]
+struct OldProject<AccountId> {
+	total_votes: u128,
+	grants: u128,
+	support_area: u128,
+	withdrew: u128,
+	name: Vec<u8>,
+	owner: AccountId,
+}
+
+/// Move rounds, projects and votes to the layout of `Releases::V2_0_0`.
+/// Old rounds get the default parameters, which are the Config constants they were using,
+/// and every vote has an identity weight of 1.
+pub fn migrate_to_v2<T: Config>() -> Weight {
+	if StorageVersion::get() != Releases::V1_0_0 {
+		return T::DbWeight::get().reads(1);
+	}
+	Projects::<T>::translate(|_, _, old: OldProject<T::AccountId>| Some(Project {
+		total_votes: old.total_votes,
+		weighted_votes: old.total_votes,
+		grants: old.grants,
+		support_area: old.support_area,
+		withdrew: old.withdrew,
+		name: old.name,
+		owner: old.owner,
+		..Default::default()
+	}));
+	let params = Module::<T>::parameters().round;
+	Rounds::translate(|round_id, old: OldRound| Some(Round {
+		ongoing: old.ongoing,
+		support_pool: old.support_pool,
+		pre_tax_support_pool: old.pre_tax_support_pool,
+		total_support_area: old.total_support_area,
+		// the tax of votes was not recorded apart, it counts as the tax of donations
+		total_tax: old.total_tax,
+		params: params.clone(),
+		project_count: Projects::<T>::iter_prefix(round_id).count() as u32,
+		..Default::default()
+	}));
+	ProjectVotes::<T>::translate(|_, _, ballots: u128| Some(VoteRecord { ballots, weight: 1, ..Default::default() }));
+	StorageVersion::put(Releases::V2_0_0);
+	// every round, project and vote is read and written once
+	T::MaximumBlockWeight::get()
+}
+
+/// Start `NextRoundId` after the highest round, rounds were opened with an explicit id before it existed.
+/// Does nothing once `NextRoundId` is set.
+pub fn seed_next_round_id<T: Config>() -> Weight {
+	if NextRoundId::exists() {
+		return T::DbWeight::get().reads(1);
+	}
+	let mut rounds = 0u64;
+	let next = Rounds::iter().fold(0u32, |next, (round_id, _)| {
+		rounds += 1;
+		next.max(round_id.saturating_add(1))
+	});
+	NextRoundId::put(next);
+	T::DbWeight::get().reads_writes(rounds + 1, 1)
+}
@@ -1,9 +1,12 @@
-use crate::{Module, Config};
+use crate::{Module, Config, MinimumBalance, IdentityLevel, IdentityLevelOf, AssetId, MultiAsset};
 use frame_system as system;
 use sp_core::H256;
-use frame_support::{impl_outer_origin, impl_outer_event, parameter_types, weights::Weight};
+use frame_support::{
+	impl_outer_origin, impl_outer_event, parameter_types, weights::Weight, codec::Encode, storage::unhashed,
+	dispatch::{DispatchResult, DispatchError}, traits::{Currency, LockIdentifier, ExistenceRequirement::KeepAlive},
+};
 use sp_runtime::{
-	Perbill, ModuleId,
+	Perbill, Percent, ModuleId,
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
 };
@@ -20,6 +23,7 @@ impl_outer_event! {
 	pub enum Event for Test {
 		system<T>,
 		pallet_balances<T>,
+		pallet_identity<T>,
 		quadratic_funding<T>,
 	}
 }
@@ -44,6 +48,14 @@ parameter_types! {
 	pub const QuadraticFundingModuleId: ModuleId = ModuleId(*b"py/quafd");
 	pub const NameMinLength: usize = 3;
 	pub const NameMaxLength: usize = 32;
+	// Accounts holding less than this can not vote
+	pub const MinVoterBalance: u64 = 100;
+	// Accounts created less than this number of blocks ago can not vote
+	pub const MinVoterAge: u64 = 5;
+	pub const TreasuryAccount: u64 = 3;
+	pub const VoteLockId: LockIdentifier = *b"qf/votes";
+	pub const RoundDeposit: u64 = 100;
+	pub const FeeShare: Percent = Percent::from_percent(50);
 }
 
 impl system::Trait for Test {
@@ -87,6 +99,29 @@ impl pallet_balances::Trait for Test {
 	type WeightInfo = ();
 }
 
+parameter_types! {
+	pub const BasicDeposit: u64 = 10;
+	pub const FieldDeposit: u64 = 1;
+	pub const SubAccountDeposit: u64 = 1;
+	pub const MaxSubAccounts: u32 = 2;
+	pub const MaxAdditionalFields: u32 = 2;
+	pub const MaxRegistrars: u32 = 20;
+}
+impl pallet_identity::Trait for Test {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	type Slashed = ();
+	type ForceOrigin = frame_system::EnsureRoot<u64>;
+	type RegistrarOrigin = frame_system::EnsureRoot<u64>;
+	type WeightInfo = ();
+}
+
 impl Config for Test {
 	type ModuleId = QuadraticFundingModuleId;
     // The Balances pallet implements the ReservableCurrency trait.
@@ -99,6 +134,9 @@ impl Config for Test {
     // No action is taken when deposits are forfeited.
     type Slashed = ();
 
+	// The tax is burned
+	type FeeDestination = ();
+
     // Use the MinNickLength from the parameter_types block.
     type NumberOfUnitPerVote = NumberOfUnit;
 
@@ -114,10 +152,80 @@ impl Config for Test {
 
 	// The maximum length of project name
 	type NameMaxLength = NameMaxLength;
+
+	// Only accounts with enough free balance can vote
+	type VoterEligibility = MinimumBalance<Test, MinVoterBalance>;
+
+	type IdentityLevels = MockIdentity;
+
+	// The deposit to open a round
+	type RoundDeposit = RoundDeposit;
+
+	// Root spends from the treasury
+	type TreasuryOrigin = frame_system::EnsureRoot<u64>;
+
+	type TreasuryAccount = TreasuryAccount;
+
+	type Assets = MockAssets;
+
+	type VoteLockId = VoteLockId;
+
+	type FeeShare = FeeShare;
+}
+
+// The native currency is Balances, tokens are kept in the test storage
+pub struct MockAssets;
+impl MockAssets {
+	fn key(id: u32, who: &u64) -> Vec<u8> {
+		(b"mock_tokens", id, who).encode()
+	}
+
+	pub fn mint(id: u32, who: &u64, amount: u64) {
+		let key = Self::key(id, who);
+		unhashed::put(&key, &(unhashed::get_or_default::<u64>(&key) + amount));
+	}
+}
+impl MultiAsset<u64> for MockAssets {
+	type Balance = u64;
+
+	fn free_balance(asset: AssetId, who: &u64) -> u64 {
+		match asset {
+			AssetId::Native => Balances::free_balance(who),
+			AssetId::Token(id) => unhashed::get_or_default(&Self::key(id, who)),
+		}
+	}
+
+	fn transfer(asset: AssetId, from: &u64, to: &u64, amount: u64) -> DispatchResult {
+		match asset {
+			AssetId::Native => <Balances as Currency<u64>>::transfer(from, to, amount, KeepAlive),
+			AssetId::Token(id) => {
+				let balance = Self::free_balance(asset, from);
+				if balance < amount {
+					return Err(DispatchError::Other("InsufficientBalance"));
+				}
+				unhashed::put(&Self::key(id, from), &(balance - amount));
+				Self::mint(id, to, amount);
+				Ok(())
+			}
+		}
+	}
+}
+
+// Account 2 is judged as reasonable, account 3 as known good, the others are anonymous
+pub struct MockIdentity;
+impl IdentityLevelOf<u64> for MockIdentity {
+	fn identity_level(who: &u64) -> IdentityLevel {
+		match who {
+			2 => IdentityLevel::Reasonable,
+			3 => IdentityLevel::KnownGood,
+			_ => IdentityLevel::Anonymous,
+		}
+	}
 }
 
 pub type System = frame_system::Module<Test>;
 pub type Balances = pallet_balances::Module<Test>;
+pub type Identity = pallet_identity::Module<Test>;
 pub type QuadraticFunding = Module<Test>;
 
 // Build genesis storage according to the mock runtime.
@@ -126,7 +234,7 @@ pub fn new_test_ext() -> sp_io::TestExternalities {
 	let mut t = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 	pallet_balances::GenesisConfig::<Test>{
 		// Total issuance will be 1000 with internal account initialized at ED.
-		balances: vec![(0, 1000), (1, 2000), (2, 3000), (3, 4000)],
+		balances: vec![(0, 1000), (1, 2000), (2, 3000), (3, 4000), (4, 50)],
 	}.assimilate_storage(&mut t).unwrap();
 	system::GenesisConfig::default().assimilate_storage::<Test>(&mut t).unwrap();
 	t.into()
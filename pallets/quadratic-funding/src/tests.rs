@@ -1,7 +1,8 @@
-use crate::{Error, mock::*};
-use frame_support::{assert_ok, assert_noop};
+use crate::{Error, Round, Rounds, Projects, ProjectVotes, StorageVersion, Releases, RoundFee, IdentityLevel, IdentityLevelOf, VoterEligibility, IdentityJudgement, MinimumBalance, MinimumAccountAge, SelfVotePolicy, RoundParams, QfParameters, ParamBounds, MAX_PROJECTS, MAX_ROUND_MANAGERS, DirectedTarget, PoolMatching, AssetId, MultiAsset, VoteMode, NegativeVotes, merkle, mock::*};
+use frame_support::{assert_ok, assert_noop, storage::{unhashed, StorageMap, StorageDoubleMap, StorageValue}, traits::{Currency, OnUnbalanced, OnNewAccount, OnRuntimeUpgrade, Get}};
+use pallet_identity::{IdentityInfo, Data, Judgement};
 use sp_core::H256;
-use sp_runtime::traits::BadOrigin;
+use sp_runtime::{Percent, traits::{BadOrigin, BlakeTwo256, Hash}};
 use super::RawEvent;
 
 /// generate a Hash for indexing project
@@ -10,7 +11,7 @@ fn get_hash(value: u128) -> H256 {
 	H256::from_slice(&slices.repeat(2))
 }
 
-fn last_event() -> RawEvent<u64, H256> {
+fn last_event() -> RawEvent<u64, H256, u64> {
 	System::events().into_iter().map(|r| r.event)
 		.filter_map(|e| {
 			if let Event::quadratic_funding(inner) = e { Some(inner) } else { None }
@@ -22,10 +23,11 @@ fn last_event() -> RawEvent<u64, H256> {
 #[test]
 fn round_control_works() {
 	new_test_ext().execute_with(|| {
-		let round_id = 1;
-		// make sure only AdminOrigin can start/end round
-		assert_noop!(QuadraticFunding::start_round(Origin::signed(1), round_id), BadOrigin);
-		assert_ok!(QuadraticFunding::start_round(Origin::root(), round_id));
+		let round_id = QuadraticFunding::next_round_id();
+		// make sure only AdminOrigin or signed accounts can start round
+		assert_noop!(QuadraticFunding::start_round(Origin::none(), None, vec![], None), BadOrigin);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_eq!(QuadraticFunding::next_round_id(), round_id + 1);
 
 		assert_noop!(QuadraticFunding::end_round(Origin::signed(1), round_id), BadOrigin);
 		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
@@ -39,22 +41,32 @@ fn register_project_works() {
 		System::set_block_number(1);
 		let hash = get_hash(1);
 		let project_name = b"name".to_vec();
-		let round_id = 1;
+		let round_id = QuadraticFunding::next_round_id();
 		// Dispatch a signed extrinsic.
 		// should start round first
-		assert_ok!(QuadraticFunding::start_round(Origin::root(), round_id));
-		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, project_name.clone()));
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, project_name.clone(), None));
 		
 		// Read pallet storage and assert an expected result.
 		// positive case
 		assert_eq!(QuadraticFunding::projects(round_id, hash).name, project_name);
 		// negative case
 		assert_noop!(
-			QuadraticFunding::register_project(Origin::signed(1), round_id, hash, project_name),
+			QuadraticFunding::register_project(Origin::signed(1), round_id, hash, project_name.clone(), None),
 			Error::<Test>::DuplicateProject
 		);
 
 		assert_eq!(Balances::free_balance(0), 1000);
+
+		// a round has a bounded number of projects
+		for i in 2..=MAX_PROJECTS {
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(i.into()), project_name.clone(), None));
+		}
+		assert_eq!(QuadraticFunding::rounds(round_id).project_count, MAX_PROJECTS);
+		assert_noop!(
+			QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(0), project_name, None),
+			Error::<Test>::TooManyProjects
+		);
 		// Deprecated! This method has been changed to rpc
 		// assert_ok!(QuadraticFunding::vote_cost(Origin::signed(1), round_id, hash, 1));
 		// assert_eq!(last_event(), RawEvent::VoteCost(hash,1));
@@ -64,10 +76,10 @@ fn register_project_works() {
 #[test]
 fn donate_works() {
 	new_test_ext().execute_with(|| {
-		let round_id = 1;
+		let round_id = QuadraticFunding::next_round_id();
 		// Dispatch a signed extrinsic.
 		// should start round first
-		assert_ok!(QuadraticFunding::start_round(Origin::root(), round_id));
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
 		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
 		// make sure the source and dest balance is right
 		assert_eq!(Balances::free_balance(0), 500);
@@ -83,21 +95,21 @@ fn donate_works() {
 #[test]
 fn vote_without_fund_works() {
 	new_test_ext().execute_with(|| {
-		let round_id = 1;
-		assert_ok!(QuadraticFunding::start_round(Origin::root(), round_id));
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
 
 		// initalize 3 projects
 		for i in 1..4 {
 			System::set_block_number(i);
 			let hash = get_hash(i.into());
 			let project_name = b"name".to_vec();
-			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, project_name.clone()));
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, project_name.clone(), None));
 			assert_eq!(QuadraticFunding::projects(round_id, hash).name, project_name);
 
 			// vote for each own's project only once, in this case there will be no fund
 			let vote = 3;
 			let expected_cost:u64 = vote * (vote + 1) / 2 * 100;
-			assert_ok!(QuadraticFunding::vote(Origin::signed(i), round_id, hash, vote.into()));
+			assert_ok!(QuadraticFunding::vote(Origin::signed(i), round_id, hash, vote.into(), None));
 			// We initialize the balance sequentially, each one got 1000*(i+1) pico
 			assert_eq!(Balances::free_balance(i), 1000*(i+1) - expected_cost);
 		}
@@ -111,8 +123,8 @@ fn vote_without_fund_works() {
 #[test]
 fn vote_with_fund_works() {
 	new_test_ext().execute_with(|| {
-		let round_id = 1;
-		assert_ok!(QuadraticFunding::start_round(Origin::root(), round_id));
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
 		// sponsor default round
 		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
 		// initalize 3 projects
@@ -120,13 +132,13 @@ fn vote_with_fund_works() {
 			System::set_block_number(i);
 			let hash = get_hash(i.into());
 			let project_name = b"name".to_vec();
-			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, project_name.clone()));
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, project_name.clone(), None));
 			assert_eq!(QuadraticFunding::projects(round_id, hash).name, project_name);
 
 			// vote to each other, the area should be 3,3,12
 			for j in 1..4 {
 				let vote = if i > 2 {2} else {1};
-				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, vote));
+				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, vote, None));
 			}
 		}
 		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).support_area, 3);
@@ -135,4 +147,1118 @@ fn vote_with_fund_works() {
 		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 18);
 		
 	});
-}
\ No newline at end of file
+}
+
+#[test]
+fn huge_ballot_is_rejected() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		let hash = get_hash(1);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, b"name".to_vec(), None));
+		// the points of the ballot do not fit in u128
+		let ballot = u64::MAX as u128 * 2;
+		assert_noop!(
+			QuadraticFunding::vote(Origin::signed(2), round_id, hash, ballot, None),
+			Error::<Test>::AmountOverflow
+		);
+		assert!(QuadraticFunding::cal_cost(0, ballot).is_err());
+		assert_eq!(QuadraticFunding::cal_cost(2, 3).ok(), Some(12));
+	});
+}
+
+#[test]
+fn vote_requires_eligibility() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		let hash = get_hash(1);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, b"name".to_vec(), None));
+		// account 4 holds less than MinVoterBalance
+		assert_noop!(
+			QuadraticFunding::vote(Origin::signed(4), round_id, hash, 1, None),
+			Error::<Test>::VoterNotEligible
+		);
+		assert_eq!(QuadraticFunding::vote_cost(4, round_id, hash, 1), 0);
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, hash, 1, None));
+		assert_eq!(QuadraticFunding::vote_cost(2, round_id, hash, 1), 2);
+	});
+}
+
+#[test]
+fn identity_judgement_works() {
+	new_test_ext().execute_with(|| {
+		type Judged = IdentityJudgement<Test>;
+		assert!(!Judged::is_eligible(&1));
+		assert_eq!(Judged::identity_level(&1), IdentityLevel::Anonymous);
+		let info = IdentityInfo { display: Data::Raw(b"one".to_vec()), ..Default::default() };
+		assert_ok!(Identity::set_identity(Origin::signed(1), info));
+		assert!(!Judged::is_eligible(&1));
+		assert_eq!(Judged::identity_level(&1), IdentityLevel::Unknown);
+
+		assert_ok!(Identity::add_registrar(Origin::root(), 0));
+		assert_ok!(Identity::add_registrar(Origin::root(), 3));
+		assert_ok!(Identity::provide_judgement(Origin::signed(0), 0, 1, Judgement::LowQuality));
+		assert!(!Judged::is_eligible(&1));
+		assert_eq!(Judged::identity_level(&1), IdentityLevel::LowQuality);
+		assert_ok!(Identity::provide_judgement(Origin::signed(3), 1, 1, Judgement::Reasonable));
+		// the best positive judgement wins
+		assert!(Judged::is_eligible(&1));
+		assert_eq!(Judged::identity_level(&1), IdentityLevel::Reasonable);
+		assert_ok!(Identity::provide_judgement(Origin::signed(0), 0, 1, Judgement::KnownGood));
+		assert_eq!(Judged::identity_level(&1), IdentityLevel::KnownGood);
+	});
+}
+
+#[test]
+fn minimum_account_age_works() {
+	new_test_ext().execute_with(|| {
+		type OldEnough = MinimumAccountAge<Test, MinVoterAge>;
+		System::set_block_number(10);
+		<QuadraticFunding as OnNewAccount<u64>>::on_new_account(&5);
+		assert_eq!(QuadraticFunding::account_birth(5), Some(10));
+		assert!(!OldEnough::is_eligible(&5));
+		// accounts without a record are created at genesis
+		assert!(OldEnough::is_eligible(&1));
+		System::set_block_number(14);
+		assert!(!OldEnough::is_eligible(&5));
+		System::set_block_number(15);
+		assert!(OldEnough::is_eligible(&5));
+		// both policies must be satisfied, account 5 has no balance
+		assert!(!<(MinimumBalance<Test, MinVoterBalance>, OldEnough)>::is_eligible(&5));
+		assert!(<(MinimumBalance<Test, MinVoterBalance>, OldEnough)>::is_eligible(&1));
+	});
+}
+
+#[test]
+fn vote_with_allowlist_works() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		let hash = get_hash(1);
+		let leaves: Vec<H256> = vec![1u64, 2].iter().map(merkle::leaf::<BlakeTwo256, _>).collect();
+		let root = merkle::root::<BlakeTwo256>(leaves.clone());
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), Some(root), vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, b"name".to_vec(), None));
+		// proof is required for the first vote
+		assert_noop!(
+			QuadraticFunding::vote(Origin::signed(2), round_id, hash, 1, None),
+			Error::<Test>::NotInAllowlist
+		);
+		assert!(!QuadraticFunding::estimate_impact(2, round_id, hash, 1, None).eligible);
+		// account 3 is not in the allowlist, the proof of account 2 doesn't work for it
+		let proof = merkle::proof::<BlakeTwo256>(leaves, 1);
+		assert!(QuadraticFunding::estimate_impact(2, round_id, hash, 1, Some(proof.clone())).eligible);
+		assert!(!QuadraticFunding::estimate_impact(3, round_id, hash, 1, Some(proof.clone())).eligible);
+		assert_noop!(
+			QuadraticFunding::vote(Origin::signed(3), round_id, hash, 1, Some(proof.clone())),
+			Error::<Test>::NotInAllowlist
+		);
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, hash, 1, Some(proof)));
+		assert!(QuadraticFunding::verified_voters(round_id, 2));
+		// later votes skip the proof
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, hash, 1, None));
+	});
+}
+
+#[test]
+fn identity_weighted_vote_works() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		let hash = get_hash(1);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_noop!(
+			QuadraticFunding::set_identity_weights(Origin::signed(1), round_id, vec![]),
+			BadOrigin
+		);
+		// weights are bounded by the global parameters
+		assert_noop!(
+			QuadraticFunding::set_identity_weights(Origin::root(), round_id, vec![(IdentityLevel::KnownGood, 11)]),
+			Error::<Test>::IdentityWeightTooHigh
+		);
+		assert_ok!(QuadraticFunding::set_identity_weights(
+			Origin::root(), round_id, vec![(IdentityLevel::Reasonable, 2), (IdentityLevel::KnownGood, 3)]
+		));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, b"name".to_vec(), None));
+		for i in 1..4 {
+			assert_ok!(QuadraticFunding::vote(Origin::signed(i), round_id, hash, 1, None));
+		}
+		// weighted votes are 1, 2 and 3, the area is 1*2 + 1*3 + 2*3
+		assert_eq!(QuadraticFunding::projects(round_id, hash).support_area, 11);
+		assert_eq!(QuadraticFunding::projects(round_id, hash).total_votes, 3);
+
+		// the weight is kept even if the table changes later
+		assert_ok!(QuadraticFunding::set_identity_weights(Origin::root(), round_id, vec![]));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, hash, 1, None));
+		// the new ballot of account 3 is weighted by 3 against the others, 3 * (1 + 2)
+		assert_eq!(QuadraticFunding::projects(round_id, hash).support_area, 20);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 20);
+	});
+}
+
+#[test]
+fn self_vote_policy_works() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		let hash = get_hash(1);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, hash, b"name".to_vec(), None));
+		assert_noop!(
+			QuadraticFunding::add_project_member(Origin::signed(2), round_id, hash, 2),
+			Error::<Test>::NotProjectOwner
+		);
+		assert_ok!(QuadraticFunding::add_project_member(Origin::signed(1), round_id, hash, 2));
+
+		assert_ok!(QuadraticFunding::set_self_vote_policy(Origin::root(), round_id, SelfVotePolicy::Forbid));
+		assert_noop!(
+			QuadraticFunding::vote(Origin::signed(1), round_id, hash, 1, None),
+			Error::<Test>::SelfVoteForbidden
+		);
+		assert_noop!(
+			QuadraticFunding::vote(Origin::signed(2), round_id, hash, 1, None),
+			Error::<Test>::SelfVoteForbidden
+		);
+
+		assert_ok!(QuadraticFunding::set_self_vote_policy(Origin::root(), round_id, SelfVotePolicy::Discount));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, hash, 2, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, hash, 1, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, hash, 1, None));
+		// only account 3 counts, there is no other side for its area
+		assert_eq!(QuadraticFunding::projects(round_id, hash).support_area, 0);
+		assert_eq!(QuadraticFunding::projects(round_id, hash).total_votes, 4);
+		assert_eq!(QuadraticFunding::projects_per_round(round_id)[0].discounted_votes, 3);
+		assert!(QuadraticFunding::project_votes(BlakeTwo256::hash_of(&(&hash, &round_id)), 1).discounted);
+		// the team is frozen once the project has votes
+		assert_noop!(
+			QuadraticFunding::remove_project_member(Origin::signed(1), round_id, hash, 2),
+			Error::<Test>::VotingStarted
+		);
+		assert_noop!(
+			QuadraticFunding::add_project_member(Origin::signed(1), round_id, hash, 3),
+			Error::<Test>::VotingStarted
+		);
+
+		// a later policy change does not affect how the recorded votes are withdrawn
+		assert_ok!(QuadraticFunding::set_self_vote_policy(Origin::root(), round_id, SelfVotePolicy::Allow));
+		assert_ok!(QuadraticFunding::unvote(Origin::signed(1), round_id, hash, 2));
+		assert_eq!(QuadraticFunding::projects(round_id, hash).total_votes, 2);
+		assert_eq!(QuadraticFunding::projects_per_round(round_id)[0].discounted_votes, 1);
+		assert_eq!(QuadraticFunding::projects(round_id, hash).support_area, 0);
+
+		// forbidding self votes does not block taking back the recorded ones
+		assert_ok!(QuadraticFunding::set_self_vote_policy(Origin::root(), round_id, SelfVotePolicy::Forbid));
+		assert_ok!(QuadraticFunding::unvote(Origin::signed(2), round_id, hash, 1));
+		assert_eq!(QuadraticFunding::projects_per_round(round_id)[0].discounted_votes, 0);
+		// voting again after a full unvote keeps the recorded discount
+		assert_ok!(QuadraticFunding::set_self_vote_policy(Origin::root(), round_id, SelfVotePolicy::Allow));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, hash, 1, None));
+		assert_eq!(QuadraticFunding::projects_per_round(round_id)[0].discounted_votes, 1);
+		assert_eq!(QuadraticFunding::projects(round_id, hash).support_area, 0);
+
+		// nor can a team change after the round
+		let other = get_hash(2);
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(3), round_id, other, b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_noop!(
+			QuadraticFunding::add_project_member(Origin::signed(3), round_id, other, 2),
+			Error::<Test>::RoundHasEnded
+		);
+	});
+}
+
+#[test]
+fn round_manager_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![1], None));
+		assert_noop!(QuadraticFunding::add_round_manager(Origin::signed(2), round_id, 2), BadOrigin);
+		assert_ok!(QuadraticFunding::add_round_manager(Origin::signed(1), round_id, 2));
+		assert_eq!(last_event(), RawEvent::RoundManagerAdded(round_id, 2));
+		assert_noop!(
+			QuadraticFunding::add_round_manager(Origin::root(), round_id, 2),
+			Error::<Test>::RoundManagerExisted
+		);
+		assert_ok!(QuadraticFunding::set_self_vote_policy(Origin::signed(2), round_id, SelfVotePolicy::Forbid));
+
+		// the managers of a round are bounded and unique
+		assert_noop!(QuadraticFunding::start_round(Origin::root(), None, vec![1, 2, 1], None), Error::<Test>::RoundManagerExisted);
+		let managers: Vec<u64> = (10..10 + MAX_ROUND_MANAGERS as u64).collect();
+		assert_noop!(QuadraticFunding::start_round(Origin::signed(1), None, managers.clone(), None), Error::<Test>::TooManyRoundManagers);
+		let other = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, managers, None));
+		assert_noop!(QuadraticFunding::add_round_manager(Origin::root(), other, 1), Error::<Test>::TooManyRoundManagers);
+
+		// admin keeps an override
+		assert_ok!(QuadraticFunding::remove_round_manager(Origin::root(), round_id, 1));
+		assert_noop!(QuadraticFunding::end_round(Origin::signed(1), round_id), BadOrigin);
+		assert_ok!(QuadraticFunding::end_round(Origin::signed(2), round_id));
+	});
+}
+
+#[test]
+fn permissionless_round_works() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		// not enough balance for the deposit
+		assert!(QuadraticFunding::start_round(Origin::signed(4), None, vec![], None).is_err());
+		assert_ok!(QuadraticFunding::start_round(Origin::signed(1), None, vec![], None));
+		assert_eq!(Balances::reserved_balance(1), 100);
+		assert_eq!(QuadraticFunding::round_managers(round_id), vec![1]);
+		// the deposit is returned after settlement
+		assert_ok!(QuadraticFunding::end_round(Origin::signed(1), round_id));
+		assert_eq!(Balances::reserved_balance(1), 0);
+		assert_eq!(Balances::free_balance(1), 2000);
+
+		// the deposit is slashed when governance takes the round down
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::signed(2), None, vec![], None));
+		assert_noop!(QuadraticFunding::cancel_round(Origin::signed(2), round_id), BadOrigin);
+		assert_ok!(QuadraticFunding::cancel_round(Origin::root(), round_id));
+		assert_eq!(Balances::reserved_balance(2), 0);
+		assert_eq!(Balances::free_balance(2), 2900);
+		assert_noop!(QuadraticFunding::donate(Origin::signed(3), round_id, 500), Error::<Test>::RoundHasEnded);
+	});
+}
+
+#[test]
+fn round_ids_are_not_reused() {
+	new_test_ext().execute_with(|| {
+		// rounds opened with an explicit id before `NextRoundId` existed, in the current layout
+		StorageVersion::put(Releases::V2_0_0);
+		Rounds::insert(0, Round { ongoing: true, ..Default::default() });
+		Rounds::insert(4, Round::default());
+		assert_noop!(QuadraticFunding::start_round(Origin::root(), None, vec![], None), Error::<Test>::RoundExisted);
+		<QuadraticFunding as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(QuadraticFunding::next_round_id(), 5);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert!(QuadraticFunding::rounds(0).ongoing);
+		assert!(QuadraticFunding::rounds(5).ongoing);
+		// it is only seeded once
+		<QuadraticFunding as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(QuadraticFunding::next_round_id(), 6);
+	});
+}
+
+#[test]
+fn storage_is_migrated_to_v2() {
+	new_test_ext().execute_with(|| {
+		let hash = get_hash(1);
+		let vote_hash = BlakeTwo256::hash_of(&(&hash, &7u32));
+		// (ongoing, support_pool, pre_tax_support_pool, total_support_area, total_tax)
+		unhashed::put(&Rounds::hashed_key_for(7), &(true, 475u128, 500u128, 2u128, 35u128));
+		// (total_votes, grants, support_area, withdrew, name, owner)
+		unhashed::put(&Projects::<Test>::hashed_key_for(7, hash), &(2u128, 190u128, 2u128, 0u128, b"name".to_vec(), 1u64));
+		unhashed::put(&ProjectVotes::<Test>::hashed_key_for(vote_hash, 2), &1u128);
+		unhashed::put(&ProjectVotes::<Test>::hashed_key_for(vote_hash, 3), &1u128);
+		<QuadraticFunding as OnRuntimeUpgrade>::on_runtime_upgrade();
+
+		assert_eq!(QuadraticFunding::storage_version(), Releases::V2_0_0);
+		let round = QuadraticFunding::rounds(7);
+		assert!(round.ongoing);
+		assert_eq!((round.support_pool, round.total_support_area, round.total_tax, round.project_count), (475, 2, 35, 1));
+		assert_eq!(round.params, QuadraticFunding::parameters().round);
+		let project = QuadraticFunding::projects(7, hash);
+		assert_eq!((project.total_votes, project.weighted_votes, project.grants, project.owner), (2, 2, 190, 1));
+		let record = QuadraticFunding::project_votes(vote_hash, 2);
+		assert_eq!((record.ballots, record.weight, record.against), (1, 1, 0));
+		assert_eq!(QuadraticFunding::next_round_id(), 8);
+
+		// the migrated round works as before, a third voter adds an area of 2 with the two others
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), 7, hash, 1, None));
+		assert_eq!(QuadraticFunding::projects(7, hash).support_area, 4);
+		// it only runs once
+		<QuadraticFunding as OnRuntimeUpgrade>::on_runtime_upgrade();
+		assert_eq!(QuadraticFunding::project_votes(vote_hash, 1).ballots, 1);
+		assert_eq!(QuadraticFunding::rounds(7).total_support_area, 4);
+	});
+}
+
+#[test]
+fn round_params_works() {
+	new_test_ext().execute_with(|| {
+		let params = RoundParams {
+			unit_of_vote: 2,
+			number_of_unit_per_vote: 100,
+			fee_ratio_per_vote: 10,
+			name_min_length: 5,
+			name_max_length: 10,
+			..Default::default()
+		};
+		// the fee can not exceed the vote amount
+		assert_noop!(
+			QuadraticFunding::start_round(Origin::signed(1), None, vec![], Some(RoundParams { fee_ratio_per_vote: 200, ..params.clone() })),
+			Error::<Test>::InvalidParams
+		);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::signed(1), None, vec![], Some(params)));
+		assert_noop!(
+			QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None),
+			Error::<Test>::ProjectNameTooShort
+		);
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"long name".to_vec(), None));
+		// fee rate is 10%
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_eq!(QuadraticFunding::rounds(round_id).total_tax, 50);
+		// each vote costs 2 * 100
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 1, None));
+		assert_eq!(Balances::free_balance(2), 2800);
+	});
+}
+
+#[test]
+fn set_parameters_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		// genesis values come from the Config constants
+		assert_eq!(QuadraticFunding::parameters().round.fee_ratio_per_vote, 5);
+		assert_eq!(QuadraticFunding::parameters().round_deposit, 100);
+		assert_eq!(QuadraticFunding::parameters().fee_share, Percent::from_percent(50));
+		let parameters = QfParameters {
+			round: RoundParams { fee_ratio_per_vote: 10, ..QuadraticFunding::parameters().round },
+			round_deposit: 200,
+			fee_share: Percent::from_percent(80),
+			..QuadraticFunding::parameters()
+		};
+		assert_noop!(QuadraticFunding::set_parameters(Origin::signed(1), parameters.clone()), BadOrigin);
+		assert_ok!(QuadraticFunding::set_parameters(Origin::root(), parameters.clone()));
+		assert_eq!(last_event(), RawEvent::ParametersChanged(parameters.round, 200));
+		assert_eq!(QuadraticFunding::parameters().fee_share, Percent::from_percent(80));
+		let info = QuadraticFunding::parameters_info();
+		assert_eq!((info.unit_of_vote, info.fee_ratio_per_vote, info.round_deposit), (1, 10, 200));
+
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::signed(1), None, vec![], None));
+		assert_eq!(Balances::reserved_balance(1), 200);
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_eq!(QuadraticFunding::rounds(round_id).total_tax, 50);
+
+		// the parameters of a round are bounded, the fee can not be zero
+		let bounds = QuadraticFunding::parameters().bounds;
+		assert_eq!(bounds.max_unit_of_vote, 1000);
+		let params = QuadraticFunding::parameters().round;
+		for invalid in vec![
+			RoundParams { unit_of_vote: 1001, ..params.clone() },
+			RoundParams { number_of_unit_per_vote: 100_001, ..params.clone() },
+			RoundParams { fee_ratio_per_vote: 0, ..params.clone() },
+			RoundParams { credits_per_voter: u32::max_value() as u128 + 1, ..params.clone() },
+		] {
+			assert_noop!(QuadraticFunding::start_round(Origin::signed(1), None, vec![], Some(invalid)), Error::<Test>::InvalidParams);
+		}
+		// new defaults must be within the new bounds
+		let parameters = QfParameters { bounds: ParamBounds { min_fee_ratio_per_vote: 20, ..bounds }, ..QuadraticFunding::parameters() };
+		assert_noop!(QuadraticFunding::set_parameters(Origin::root(), parameters), Error::<Test>::InvalidParams);
+	});
+}
+
+#[test]
+fn tax_is_paid_at_settlement() {
+	new_test_ext().execute_with(|| {
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 1, None));
+		// 25 from the donation, 5 from the vote
+		assert_eq!(QuadraticFunding::round_fees(round_id), vec![RoundFee { asset: AssetId::Native, total_tax: 30, tax_paid: 0 }]);
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::round_fees(round_id), vec![RoundFee { asset: AssetId::Native, total_tax: 30, tax_paid: 30 }]);
+		// the vote without fee goes to the owner, the pool stays as there is no support area
+		assert_eq!(Balances::free_balance(1), 2095);
+		assert_eq!(Balances::free_balance(&QuadraticFunding::account_id()), 475);
+	});
+}
+
+#[test]
+fn fund_round_from_treasury_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_noop!(QuadraticFunding::fund_round_from_treasury(Origin::signed(1), round_id, 1000), BadOrigin);
+		assert_ok!(QuadraticFunding::fund_round_from_treasury(Origin::root(), round_id, 1000));
+		assert_eq!(last_event(), RawEvent::TreasuryFunded(round_id, 1000));
+		assert_eq!(Balances::free_balance(3), 3000);
+		assert_eq!(Balances::free_balance(&QuadraticFunding::account_id()), 1000);
+		// the whole amount goes to the pool
+		assert_eq!(QuadraticFunding::rounds(round_id).pre_tax_support_pool, 1000);
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 1000);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_tax, 0);
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_noop!(QuadraticFunding::fund_round_from_treasury(Origin::root(), round_id, 1000), Error::<Test>::RoundHasEnded);
+	});
+}
+
+#[test]
+fn designated_round_receives_imbalances() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		// nothing is designated, the imbalance is burned
+		let issuance = Balances::total_issuance();
+		QuadraticFunding::on_unbalanced(Balances::issue(100));
+		assert_eq!(Balances::total_issuance(), issuance);
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 0);
+
+		assert_noop!(QuadraticFunding::set_designated_round(Origin::signed(1), Some(round_id)), BadOrigin);
+		assert_noop!(QuadraticFunding::set_designated_round(Origin::root(), Some(round_id + 1)), Error::<Test>::RoundNotExist);
+		assert_ok!(QuadraticFunding::set_designated_round(Origin::root(), Some(round_id)));
+		QuadraticFunding::on_unbalanced(Balances::issue(100));
+		assert_eq!(last_event(), RawEvent::PoolFunded(round_id, 100));
+		assert_eq!(Balances::total_issuance(), issuance + 100);
+		assert_eq!(Balances::free_balance(&QuadraticFunding::account_id()), 100);
+		assert_eq!(QuadraticFunding::rounds(round_id).pre_tax_support_pool, 100);
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 100);
+
+		// an ended round is not credited anymore
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		QuadraticFunding::on_unbalanced(Balances::issue(100));
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 100);
+	});
+}
+
+#[test]
+fn unallocated_funds_roll_over() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		for _ in 0..3 {
+			assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		}
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		for i in 1..4 {
+			let hash = get_hash(i.into());
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, b"name".to_vec(), None));
+			for j in 1..4 {
+				let vote = if i > 2 {2} else {1};
+				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, vote, None));
+			}
+		}
+		let account = QuadraticFunding::account_id();
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native));
+		assert_noop!(QuadraticFunding::rollover(Origin::root(), round_id, round_id + 1), Error::<Test>::RoundNotEnded);
+
+		// the pool is 475 and the area is 18, 7 is left from the division
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 7);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(3)).withdrew, QuadraticFunding::projects(round_id, get_hash(3)).grants);
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native));
+
+		// admin moves it to the next round
+		assert_noop!(QuadraticFunding::rollover(Origin::signed(1), round_id, round_id + 1), BadOrigin);
+		assert_ok!(QuadraticFunding::rollover(Origin::root(), round_id, round_id + 1));
+		assert_eq!(last_event(), RawEvent::RolledOver(round_id, round_id + 1, 7));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 0);
+		assert_eq!(QuadraticFunding::rounds(round_id + 1).support_pool, 7);
+		assert_noop!(QuadraticFunding::rollover(Origin::root(), round_id, round_id + 1), Error::<Test>::NoUnallocatedFunds);
+
+		// without support area, the whole pool goes to the successor at settlement
+		assert_noop!(QuadraticFunding::set_successor(Origin::root(), round_id + 1, Some(round_id + 1)), Error::<Test>::InvalidSuccessor);
+		assert_ok!(QuadraticFunding::set_successor(Origin::root(), round_id + 1, Some(round_id + 2)));
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id + 1));
+		assert_eq!(QuadraticFunding::rounds(round_id + 1).unallocated, 0);
+		assert_eq!(QuadraticFunding::rounds(round_id + 2).support_pool, 7);
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native));
+	});
+}
+
+#[test]
+fn sponsor_refund_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		// the pallet account is created at genesis with the existential deposit
+		let account = QuadraticFunding::account_id();
+		Balances::make_free_balance_be(&account, 1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(1), round_id, 300));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(1), round_id, 200));
+		assert_eq!(QuadraticFunding::sponsors(round_id, 1), 500);
+		assert_noop!(QuadraticFunding::claim_sponsor_refund(Origin::signed(0), round_id), Error::<Test>::RoundNotEnded);
+
+		// there is no project, the pool of 950 is unallocated
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 950);
+		assert_noop!(QuadraticFunding::claim_sponsor_refund(Origin::signed(2), round_id), Error::<Test>::NotSponsor);
+		assert_ok!(QuadraticFunding::claim_sponsor_refund(Origin::signed(0), round_id));
+		assert_eq!(last_event(), RawEvent::SponsorRefunded(round_id, 0, 475));
+		assert_eq!(Balances::free_balance(0), 975);
+		assert_noop!(QuadraticFunding::claim_sponsor_refund(Origin::signed(0), round_id), Error::<Test>::NotSponsor);
+		assert_ok!(QuadraticFunding::claim_sponsor_refund(Origin::signed(1), round_id));
+		assert_eq!(Balances::free_balance(1), 1975);
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 0);
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native) + 1);
+	});
+}
+
+#[test]
+fn cancel_round_keeps_vote_payments() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		// the pallet account is created at genesis with the existential deposit
+		let account = QuadraticFunding::account_id();
+		Balances::make_free_balance_be(&account, 1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { negative_votes: NegativeVotes::ToPool, ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 2, None));
+		assert_ok!(QuadraticFunding::vote_against(Origin::signed(3), round_id, get_hash(1), 1, None));
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 475 + 95);
+
+		// the sponsor gets the whole donation back, the votes are kept for their voters minus the fee
+		assert_ok!(QuadraticFunding::cancel_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 500);
+		assert_eq!(QuadraticFunding::rounds(round_id).voter_refunds, 285 + 95);
+		assert_eq!(QuadraticFunding::rounds(round_id).tax_paid, 20);
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native) + 1);
+		assert_ok!(QuadraticFunding::claim_sponsor_refund(Origin::signed(0), round_id));
+		assert_eq!(Balances::free_balance(0), 1000);
+		assert_noop!(QuadraticFunding::rollover(Origin::root(), round_id, round_id + 1), Error::<Test>::NoUnallocatedFunds);
+		assert_eq!(Balances::free_balance(&account), 285 + 95 + 1);
+
+		// each voter claims its own votes back
+		assert_noop!(QuadraticFunding::claim_vote_refund(Origin::signed(2), round_id + 1, get_hash(1)), Error::<Test>::RoundNotEnded);
+		assert_noop!(QuadraticFunding::claim_vote_refund(Origin::signed(1), round_id, get_hash(1)), Error::<Test>::NoVoteRefund);
+		assert_ok!(QuadraticFunding::claim_vote_refund(Origin::signed(2), round_id, get_hash(1)));
+		assert_eq!(last_event(), RawEvent::VoteRefunded(round_id, get_hash(1), 2, 285));
+		assert_eq!(Balances::free_balance(2), 3000 - 15);
+		assert_noop!(QuadraticFunding::claim_vote_refund(Origin::signed(2), round_id, get_hash(1)), Error::<Test>::NoVoteRefund);
+		assert_ok!(QuadraticFunding::claim_vote_refund(Origin::signed(3), round_id, get_hash(1)));
+		assert_eq!(Balances::free_balance(3), 4000 - 5);
+		assert_eq!(QuadraticFunding::rounds(round_id).voter_refunds, 0);
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native) + 1);
+	});
+}
+
+#[test]
+fn directed_donation_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		for i in 1..4 {
+			let hash = get_hash(i.into());
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, b"name".to_vec(), None));
+			for j in 1..4 {
+				let vote = if i > 2 {2} else {1};
+				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, vote, None));
+			}
+		}
+		assert_noop!(QuadraticFunding::set_project_tags(Origin::signed(2), round_id, get_hash(1), vec![b"tools".to_vec()]), Error::<Test>::NotProjectOwner);
+		assert_ok!(QuadraticFunding::set_project_tags(Origin::signed(1), round_id, get_hash(1), vec![b"tools".to_vec()]));
+		assert_noop!(QuadraticFunding::donate_directed(Origin::signed(3), round_id, vec![], 500), Error::<Test>::InvalidTargets);
+
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		let tools = vec![DirectedTarget::Tag(b"tools".to_vec())];
+		assert_ok!(QuadraticFunding::donate_directed(Origin::signed(3), round_id, tools.clone(), 300));
+		assert_ok!(QuadraticFunding::donate_directed(Origin::signed(2), round_id, tools, 200));
+		assert_eq!(last_event(), RawEvent::DirectedDonateSucceed(round_id, 2, 0, 200));
+		// donations with the same targets share the pool, the general pool is untouched
+		assert_eq!(QuadraticFunding::directed_pools(round_id).len(), 1);
+		assert_eq!(QuadraticFunding::directed_pools(round_id)[0].amount, 475);
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 475);
+		// the targeted tag is frozen, other tags can still change
+		assert_noop!(QuadraticFunding::set_project_tags(Origin::signed(3), round_id, get_hash(3), vec![b"tools".to_vec()]), Error::<Test>::TagFrozen);
+		assert_noop!(QuadraticFunding::set_project_tags(Origin::signed(1), round_id, get_hash(1), vec![]), Error::<Test>::TagFrozen);
+		assert_ok!(QuadraticFunding::set_project_tags(Origin::signed(1), round_id, get_hash(1), vec![b"tools".to_vec(), b"docs".to_vec()]));
+
+		// area of the projects is 3, 3, 12, only the first one is tagged
+		assert_eq!(QuadraticFunding::project_matching(round_id, get_hash(1)), vec![
+			PoolMatching { pool: None, matching: 78, asset: AssetId::Native },
+			PoolMatching { pool: Some(0), matching: 474, asset: AssetId::Native },
+		]);
+		assert_eq!(
+			QuadraticFunding::project_matching(round_id, get_hash(2)),
+			vec![PoolMatching { pool: None, matching: 78, asset: AssetId::Native }]
+		);
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).grants, 285 + 78 + 474);
+		// 7 is left from the general pool and 1 from the directed pool
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 8);
+		assert_eq!(Balances::free_balance(&QuadraticFunding::account_id()), QuadraticFunding::outstanding_obligations(AssetId::Native));
+	});
+}
+
+#[test]
+fn tracks_have_separate_pools() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_noop!(QuadraticFunding::add_track(Origin::signed(1), round_id, b"infra".to_vec()), BadOrigin);
+		assert_ok!(QuadraticFunding::add_track(Origin::root(), round_id, b"infra".to_vec()));
+		assert_ok!(QuadraticFunding::add_track(Origin::root(), round_id, b"events".to_vec()));
+		assert_eq!(last_event(), RawEvent::TrackAdded(round_id, 1));
+		assert_noop!(
+			QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), Some(2)),
+			Error::<Test>::TrackNotExist
+		);
+		// project 1 is in the first track, the others in the second
+		for i in 1..4 {
+			let hash = get_hash(i.into());
+			let track = if i == 1 {0} else {1};
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, b"name".to_vec(), Some(track)));
+			for j in 1..4 {
+				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, 1, None));
+			}
+		}
+		assert_ok!(QuadraticFunding::donate_to_track(Origin::signed(0), round_id, 0, 500));
+		assert_ok!(QuadraticFunding::donate_to_track(Origin::signed(3), round_id, 1, 500));
+		assert_noop!(QuadraticFunding::donate_to_track(Origin::signed(3), round_id, 2, 500), Error::<Test>::TrackNotExist);
+
+		// each project has an area of 3, counted in its track only
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 0);
+		assert_eq!(QuadraticFunding::tracks(round_id)[0].total_support_area, 3);
+		assert_eq!(QuadraticFunding::tracks(round_id)[1].total_support_area, 6);
+		assert_eq!(QuadraticFunding::tracks(round_id)[1].support_pool, 475);
+		let ranks = QuadraticFunding::projects_per_round(round_id);
+		let first = ranks.iter().find(|p| p.project_id == get_hash(1)).unwrap();
+		assert_eq!((first.track, first.support_grants), (Some(0), 474));
+		let second = ranks.iter().find(|p| p.project_id == get_hash(2)).unwrap();
+		assert_eq!((second.track, second.support_grants), (Some(1), 237));
+
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 2);
+		assert_eq!(Balances::free_balance(&QuadraticFunding::account_id()), QuadraticFunding::outstanding_obligations(AssetId::Native));
+	});
+}
+
+#[test]
+fn matching_in_another_asset_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let token = AssetId::Token(1);
+		// the pallet account is created at genesis with the existential deposit
+		let account = QuadraticFunding::account_id();
+		Balances::make_free_balance_be(&account, 1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { matching_asset: token, ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		for i in 1..4 {
+			let hash = get_hash(i.into());
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, b"name".to_vec(), None));
+			for j in 1..4 {
+				let vote = if i > 2 {2} else {1};
+				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, vote, None));
+			}
+		}
+		// sponsors need the token
+		assert!(QuadraticFunding::donate(Origin::signed(0), round_id, 500).is_err());
+		MockAssets::mint(1, &0, 500);
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_eq!(MockAssets::free_balance(token, &account), 500);
+		assert_eq!(Balances::free_balance(&account), 1501);
+		assert_eq!(QuadraticFunding::outstanding_obligations(token), 500);
+		assert_eq!(QuadraticFunding::outstanding_obligations(AssetId::Native), 1500);
+		let ranks = QuadraticFunding::projects_per_round(round_id);
+		let third = ranks.iter().find(|p| p.project_id == get_hash(3)).unwrap();
+		assert_eq!((third.grants, third.vote_asset), (855, AssetId::Native));
+		assert_eq!((third.support_grants, third.matching_asset), (312, token));
+
+		// fees from the native currency are burned, fees of the token go to the treasury account
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::round_fees(round_id), vec![
+			RoundFee { asset: token, total_tax: 25, tax_paid: 25 },
+			RoundFee { asset: AssetId::Native, total_tax: 75, tax_paid: 75 },
+		]);
+		assert_eq!(MockAssets::free_balance(token, &2), 78);
+		// account 3 is also the treasury account
+		assert_eq!(MockAssets::free_balance(token, &TreasuryAccount::get()), 312 + 25);
+		assert_eq!(Balances::free_balance(3), 4000 - 500 + 855);
+		// nothing of the votes is left, the rounding dust of the pool stays unallocated in the token
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 7);
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated_votes, 0);
+		assert_eq!(MockAssets::free_balance(token, &account), QuadraticFunding::outstanding_obligations(token));
+		assert_eq!(Balances::free_balance(&account), QuadraticFunding::outstanding_obligations(AssetId::Native) + 1);
+	});
+}
+
+#[test]
+fn unallocated_votes_roll_over() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { matching_asset: AssetId::Token(1), ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 1, None));
+		// the pallet account has no existential deposit, the grant of 95 can not be paid out
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 0);
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated_votes, 95);
+
+		// only a round matching in the vote asset can receive it
+		let other = QuadraticFunding::next_round_id();
+		let params = RoundParams { vote_asset: AssetId::Token(2), matching_asset: AssetId::Token(2), ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_noop!(QuadraticFunding::rollover(Origin::root(), round_id, other), Error::<Test>::AssetMismatch);
+		let successor = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::rollover(Origin::root(), round_id, successor));
+		assert_eq!(last_event(), RawEvent::RolledOver(round_id, successor, 95));
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated_votes, 0);
+		assert_eq!(QuadraticFunding::rounds(successor).support_pool, 95);
+		assert_eq!(Balances::free_balance(&QuadraticFunding::account_id()), QuadraticFunding::outstanding_obligations(AssetId::Native));
+	});
+}
+
+#[test]
+fn lock_mode_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { vote_mode: VoteMode::Lock, ..QuadraticFunding::parameters().round };
+		assert_noop!(
+			QuadraticFunding::start_round(Origin::root(), None, vec![], Some(RoundParams { vote_asset: AssetId::Token(1), ..params.clone() })),
+			Error::<Test>::InvalidParams
+		);
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params.clone())));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		for i in 1..4 {
+			let hash = get_hash(i.into());
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, hash, b"name".to_vec(), None));
+			for j in 1..4 {
+				let vote = if i > 2 {2} else {1};
+				assert_ok!(QuadraticFunding::vote(Origin::signed(j), round_id, hash, vote, None));
+			}
+		}
+		// votes are locked, not paid, but still count quadratically
+		assert_eq!(Balances::free_balance(1), 2000);
+		assert_eq!(Balances::locks(&1)[0].amount, 500);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(3)).grants, 0);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 18);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_tax, 25);
+		// the lock can not exceed the free balance
+		assert_noop!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(1), 20, None), Error::<Test>::InsufficientBalanceToLock);
+
+		// locks add up over rounds
+		let other = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(2), other, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), other, get_hash(1), 1, None));
+		assert_eq!(QuadraticFunding::total_locked(1), 600);
+
+		// votes stay locked until the round ends
+		assert_noop!(QuadraticFunding::unlock(Origin::signed(3), round_id), Error::<Test>::RoundNotEnded);
+		// only the pool is distributed, each voter releases its lock after settlement
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(Balances::free_balance(3), 4000 + 312);
+		assert_eq!(Balances::locks(&3)[0].amount, 500);
+		assert_ok!(QuadraticFunding::unlock(Origin::signed(3), round_id));
+		assert_eq!(last_event(), RawEvent::VotesUnlocked(round_id, 3, 500));
+		assert_eq!(Balances::locks(&3).len(), 0);
+		assert_noop!(QuadraticFunding::unlock(Origin::signed(3), round_id), Error::<Test>::NothingLocked);
+		assert_ok!(QuadraticFunding::unlock(Origin::signed(1), round_id));
+		assert_eq!(Balances::locks(&1)[0].amount, 100);
+		assert_ok!(QuadraticFunding::cancel_round(Origin::root(), other));
+		assert_ok!(QuadraticFunding::unlock(Origin::signed(1), other));
+		assert_eq!(Balances::locks(&1).len(), 0);
+		assert_eq!(QuadraticFunding::total_locked(1), 0);
+	});
+}
+
+#[test]
+fn voice_credits_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { vote_mode: VoteMode::Credits, credits_per_voter: 15, ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![1], Some(params)));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(2), round_id, get_hash(2), b"name".to_vec(), None));
+
+		// only the admin can set the credits of voters, within credits_per_voter
+		assert_noop!(QuadraticFunding::allocate_credits(Origin::signed(1), round_id, vec![(1, 10)]), BadOrigin);
+		assert_noop!(QuadraticFunding::allocate_credits(Origin::root(), round_id, vec![(1, 10), (2, 16)]), Error::<Test>::TooManyCredits);
+		assert_ok!(QuadraticFunding::allocate_credits(Origin::root(), round_id, vec![(1, 10)]));
+		assert_eq!(last_event(), RawEvent::CreditsAllocated(round_id, 1, 10));
+		// 3 ballots cost 6 credits, the tokens are untouched
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(1), 3, None));
+		assert_eq!(QuadraticFunding::voice_credits(round_id, 1), Some(4));
+		assert_eq!(Balances::free_balance(1), 2000);
+		assert_noop!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(2), 3, None), Error::<Test>::InsufficientCredits);
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(2), 2, None));
+		assert_eq!(QuadraticFunding::voice_credits(round_id, 1), Some(1));
+
+		// other voters get credits_per_voter, which can not change anymore
+		assert_noop!(QuadraticFunding::allocate_credits(Origin::root(), round_id, vec![(2, 5)]), Error::<Test>::VotingStarted);
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(2), 5, None));
+		assert_eq!(QuadraticFunding::voice_credits(round_id, 2), Some(0));
+
+		// votes are 3 and 7, the pool of 475 is split by vote share
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).grants, 3 * 47);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).grants, 7 * 47);
+		assert_eq!(Balances::free_balance(1), 2000 + 3 * 47);
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 475 - 470);
+	});
+}
+
+#[test]
+fn contribution_mode_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { vote_mode: VoteMode::Contribution, ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(2), round_id, get_hash(2), b"name".to_vec(), None));
+		assert_noop!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(1), 1, None), Error::<Test>::WrongVoteMode);
+		assert_noop!(QuadraticFunding::contribute(Origin::signed(3), round_id, get_hash(1), 0, None), Error::<Test>::InvalidContribution);
+
+		// square roots are 4 and 3 for project 1, 10 and 2 for project 2
+		assert_ok!(QuadraticFunding::contribute(Origin::signed(2), round_id, get_hash(1), 16, None));
+		assert_ok!(QuadraticFunding::contribute(Origin::signed(3), round_id, get_hash(1), 9, None));
+		assert_ok!(QuadraticFunding::contribute(Origin::signed(3), round_id, get_hash(2), 100, None));
+		assert_ok!(QuadraticFunding::contribute(Origin::signed(0), round_id, get_hash(2), 4, None));
+		assert_eq!(last_event(), RawEvent::ContributeSucceed(get_hash(2), 0, 4));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).support_area, 12);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).support_area, 20);
+
+		// 5 more makes the root of account 0 grow to 3, the estimate does not change the state
+		assert_eq!(QuadraticFunding::contribution_impact(0, round_id, get_hash(2), 5), (20 * (475 / 32), 30 * (475 / 42)));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).support_area, 20);
+		assert_ok!(QuadraticFunding::contribute(Origin::signed(0), round_id, get_hash(2), 5, None));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).support_area, 30);
+		assert_eq!(QuadraticFunding::contributions(BlakeTwo256::hash_of(&(&get_hash(2), &round_id)), 0), 9);
+
+		// only the contribution of 100 pays a fee
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).grants, 25 + 12 * 11);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).grants, 104 + 30 * 11);
+		assert_eq!(Balances::free_balance(2), 3000 - 16 + 104 + 30 * 11);
+		assert_eq!(QuadraticFunding::rounds(round_id).unallocated, 475 - 42 * 11);
+	});
+}
+
+#[test]
+fn contribution_fee_is_taken_on_the_total() {
+	new_test_ext().execute_with(|| {
+		// the pallet account is created at genesis with the existential deposit
+		Balances::make_free_balance_be(&QuadraticFunding::account_id(), 1);
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { vote_mode: VoteMode::Contribution, ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		// a fee of 5% rounds down to 0 for each contribution of 10, but not for their total of 40
+		for _ in 0..4 {
+			assert_ok!(QuadraticFunding::contribute(Origin::signed(3), round_id, get_hash(1), 10, None));
+		}
+		assert_eq!(QuadraticFunding::rounds(round_id).vote_tax, 2);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).grants, 38);
+
+		// the refund is the total minus its fee, nothing is left in voter refunds
+		assert_ok!(QuadraticFunding::cancel_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::rounds(round_id).voter_refunds, 38);
+		assert_ok!(QuadraticFunding::claim_vote_refund(Origin::signed(3), round_id, get_hash(1)));
+		assert_eq!(Balances::free_balance(3), 4000 - 40 + 38);
+		assert_eq!(QuadraticFunding::rounds(round_id).voter_refunds, 0);
+	});
+}
+
+#[test]
+fn negative_votes_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_noop!(QuadraticFunding::vote_against(Origin::signed(2), round_id, get_hash(1), 1, None), Error::<Test>::NegativeVotesDisabled);
+		assert_eq!(QuadraticFunding::vote_against_cost(2, round_id, get_hash(1), 1), 0);
+
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { negative_votes: NegativeVotes::ToPool, ..QuadraticFunding::parameters().round };
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(2), round_id, get_hash(2), b"name".to_vec(), None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 2, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(1), 2, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(2), 1, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(2), 1, None));
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 5);
+
+		// one weighted ballot against project 1 takes its 2 weighted votes for away, its area is floored at zero
+		assert_ok!(QuadraticFunding::vote_against(Origin::signed(0), round_id, get_hash(1), 1, None));
+		assert_eq!(last_event(), RawEvent::VoteAgainstSucceed(get_hash(1), 0, 1));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).votes_against, 1);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 1);
+		// the payment goes to the pool minus the fee
+		assert_eq!(Balances::free_balance(0), 1000 - 500 - 100);
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 475 + 95);
+		assert_eq!(QuadraticFunding::vote_against_cost(0, round_id, get_hash(1), 1), 2);
+		assert_eq!(QuadraticFunding::vote_cost(0, round_id, get_hash(1), 1), 1);
+		assert_noop!(QuadraticFunding::vote(Origin::signed(0), round_id, get_hash(1), 1, None), Error::<Test>::ConflictingVote);
+		assert_noop!(QuadraticFunding::vote_against(Origin::signed(2), round_id, get_hash(1), 1, None), Error::<Test>::ConflictingVote);
+		let ranks = QuadraticFunding::projects_per_round(round_id);
+		assert_eq!(ranks.iter().find(|p| p.project_id == get_hash(1)).unwrap().votes_against, 1);
+
+		assert_ok!(QuadraticFunding::end_round(Origin::root(), round_id));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).grants, 570);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).grants, 190 + 570);
+		assert_eq!(Balances::free_balance(2), 3000 - 300 + 760);
+
+		// burned votes leave the pool untouched
+		let round_id = QuadraticFunding::next_round_id();
+		let params = RoundParams { negative_votes: NegativeVotes::Burn, ..QuadraticFunding::parameters().round };
+		assert_noop!(
+			QuadraticFunding::start_round(Origin::root(), None, vec![], Some(RoundParams { vote_asset: AssetId::Token(1), ..params.clone() })),
+			Error::<Test>::InvalidParams
+		);
+		// only paid votes can be burned
+		for mode in vec![VoteMode::Lock, VoteMode::Credits, VoteMode::Contribution] {
+			assert_noop!(
+				QuadraticFunding::start_round(Origin::root(), None, vec![], Some(RoundParams { vote_mode: mode, ..params.clone() })),
+				Error::<Test>::InvalidParams
+			);
+		}
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], Some(params)));
+		assert_ok!(QuadraticFunding::register_project(Origin::signed(1), round_id, get_hash(1), b"name".to_vec(), None));
+		let issuance = Balances::total_issuance();
+		assert_ok!(QuadraticFunding::vote_against(Origin::signed(2), round_id, get_hash(1), 1, None));
+		assert_eq!(Balances::total_issuance(), issuance - 100);
+		assert_eq!(QuadraticFunding::rounds(round_id).support_pool, 0);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_tax, 0);
+	});
+}
+
+#[test]
+fn unvote_and_move_votes_work() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		for i in 1..4 {
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, get_hash(i.into()), b"name".to_vec(), None));
+		}
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 3, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(1), 2, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(2), 1, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(2), 1, None));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).support_area, 6);
+
+		// the third ballot cost 3 votes, the fee is kept
+		assert_noop!(QuadraticFunding::unvote(Origin::signed(2), round_id, get_hash(1), 4), Error::<Test>::InsufficientBallots);
+		assert_ok!(QuadraticFunding::unvote(Origin::signed(2), round_id, get_hash(1), 1));
+		assert_eq!(last_event(), RawEvent::VoteWithdrawn(get_hash(1), 2, 1, 285));
+		assert_eq!(Balances::free_balance(2), 3000 - 600 + 285);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).support_area, 4);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).grants, 570 + 285 - 285);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 5);
+
+		// moving is atomic
+		assert_noop!(QuadraticFunding::move_votes(Origin::signed(2), round_id, get_hash(1), get_hash(4), 2), Error::<Test>::ProjectNotExist);
+		assert_ok!(QuadraticFunding::move_votes(Origin::signed(2), round_id, get_hash(1), get_hash(3), 2));
+		assert_eq!(Balances::free_balance(2), 3000 - 600 + 285 + 285 - 300);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).total_votes, 2);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).support_area, 0);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(3)).total_votes, 2);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 1);
+	});
+}
+
+#[test]
+fn vote_many_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		for i in 1..4 {
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, get_hash(i.into()), b"name".to_vec(), None));
+		}
+		assert_noop!(QuadraticFunding::vote_many(Origin::signed(1), round_id, vec![], None), Error::<Test>::InvalidBatch);
+		assert_noop!(
+			QuadraticFunding::vote_many(Origin::signed(1), round_id, vec![(get_hash(1), 1); 17], None),
+			Error::<Test>::InvalidBatch
+		);
+		assert_noop!(
+			QuadraticFunding::vote_many(Origin::signed(1), round_id, vec![(get_hash(1), 1), (get_hash(1), 1)], None),
+			Error::<Test>::InvalidBatch
+		);
+		// nothing is voted if one of the votes fails
+		assert_noop!(
+			QuadraticFunding::vote_many(Origin::signed(1), round_id, vec![(get_hash(1), 1), (get_hash(4), 1)], None),
+			Error::<Test>::ProjectNotExist
+		);
+		assert_noop!(
+			QuadraticFunding::vote_many(Origin::signed(0), round_id, vec![(get_hash(1), 1), (get_hash(2), 5)], None),
+			pallet_balances::Error::<Test>::InsufficientBalance
+		);
+
+		assert_ok!(QuadraticFunding::vote_many(Origin::signed(1), round_id, vec![(get_hash(1), 1), (get_hash(2), 1), (get_hash(3), 2)], None));
+		assert_eq!(Balances::free_balance(1), 2000 - 500);
+		assert_eq!(last_event(), RawEvent::VoteSucceed(get_hash(3), 1, 2));
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(3)).total_votes, 2);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_tax, 25);
+		assert_eq!(QuadraticFunding::batch_vote_cost(1, round_id, vec![(get_hash(1), 1), (get_hash(3), 1)]), 2 + 3);
+	});
+}
+
+#[test]
+fn vote_with_budget_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		for i in 1..3 {
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, get_hash(i.into()), b"name".to_vec(), None));
+		}
+		// 10 votes split 2:1 are 3 and 1 ballots, the rest buys one more ballot for project 2
+		let weights = vec![(get_hash(1), 2), (get_hash(2), 1), (get_hash(3), 1)];
+		assert_eq!(QuadraticFunding::allocate_budget(1, round_id, 1000, weights.clone()), vec![(get_hash(1), 3), (get_hash(2), 2)]);
+
+		// the ballot already voted makes the next ones of project 2 more expensive
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(2), 1, None));
+		assert_eq!(QuadraticFunding::allocate_budget(1, round_id, 1000, weights.clone()), vec![(get_hash(1), 3), (get_hash(2), 1)]);
+		assert_noop!(QuadraticFunding::vote_with_budget(Origin::signed(1), round_id, 50, weights.clone(), None), Error::<Test>::BudgetTooLow);
+		assert_ok!(QuadraticFunding::vote_with_budget(Origin::signed(1), round_id, 1000, weights, None));
+		assert_eq!(Balances::free_balance(1), 2000 - 100 - 800);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(1)).total_votes, 3);
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).total_votes, 2);
+	});
+}
+
+#[test]
+fn estimate_impact_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let round_id = QuadraticFunding::next_round_id();
+		assert_ok!(QuadraticFunding::start_round(Origin::root(), None, vec![], None));
+		assert_ok!(QuadraticFunding::donate(Origin::signed(0), round_id, 500));
+		for i in 1..3 {
+			assert_ok!(QuadraticFunding::register_project(Origin::signed(i), round_id, get_hash(i.into()), b"name".to_vec(), None));
+		}
+		assert_ok!(QuadraticFunding::vote(Origin::signed(2), round_id, get_hash(1), 1, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(1), 1, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(1), round_id, get_hash(2), 1, None));
+		assert_ok!(QuadraticFunding::vote(Origin::signed(3), round_id, get_hash(2), 1, None));
+		let events = System::events().len();
+
+		// a vote of account 2 makes the area of project 2 grow from 1 to 3 out of 4
+		let impact = QuadraticFunding::estimate_impact(2, round_id, get_hash(2), 1, None);
+		assert!(impact.eligible);
+		assert_eq!(impact.support_area, 3);
+		assert_eq!(impact.matching, 3 * (475 / 4));
+		assert!(impact.changes.contains(&(get_hash(1), 118 - 237)));
+		assert!(impact.changes.contains(&(get_hash(2), 354 - 237)));
+		// nothing is changed
+		assert_eq!(QuadraticFunding::projects(round_id, get_hash(2)).support_area, 1);
+		assert_eq!(QuadraticFunding::rounds(round_id).total_support_area, 2);
+		assert_eq!(Balances::free_balance(2), 3000 - 100);
+		assert_eq!(System::events().len(), events);
+
+		// votes which would fail are not eligible and have no impact
+		assert_eq!(QuadraticFunding::estimate_impact(2, round_id, get_hash(3), 1, None).changes, vec![]);
+		assert_eq!(QuadraticFunding::estimate_impact(2, round_id, get_hash(2), -1, None).changes, vec![]);
+		assert!(!QuadraticFunding::estimate_impact(2, round_id + 1, get_hash(2), 1, None).eligible);
+		// account 4 holds less than MinVoterBalance
+		assert_eq!(QuadraticFunding::estimate_impact(4, round_id, get_hash(2), 1, None), Default::default());
+	});
+}
@@ -7,13 +7,13 @@
 include!(concat!(env!("OUT_DIR"), "/wasm_binary.rs"));
 
 use sp_std::prelude::*;
-use sp_core::{crypto::KeyTypeId, OpaqueMetadata};
+use sp_core::{crypto::KeyTypeId, OpaqueMetadata, u32_trait::{_1, _2}};
 use sp_runtime::{
 	ApplyExtrinsicResult, generic, create_runtime_str, impl_opaque_keys, MultiSignature,
 	transaction_validity::{TransactionValidity, TransactionSource}, ModuleId,
 };
 use sp_runtime::traits::{
-	BlakeTwo256, Block as BlockT, Verify, IdentifyAccount, NumberFor, Saturating,
+	BlakeTwo256, Block as BlockT, Verify, IdentifyAccount, NumberFor, Saturating, StaticLookup,
 };
 use sp_api::impl_runtime_apis;
 use sp_consensus_aura::sr25519::AuthorityId as AuraId;
@@ -28,11 +28,15 @@ use sp_version::NativeVersion;
 pub use sp_runtime::BuildStorage;
 pub use pallet_timestamp::Call as TimestampCall;
 pub use pallet_balances::Call as BalancesCall;
-pub use sp_runtime::{Permill, Perbill};
-use frame_system::{EnsureRoot};
+pub use sp_runtime::{Permill, Perbill, Percent};
+use frame_system::{EnsureRoot, EnsureOneOf};
 pub use frame_support::{
 	construct_runtime, parameter_types, StorageValue, 
-	traits::{KeyOwnerProofSystem, Randomness},
+	traits::{
+		KeyOwnerProofSystem, Randomness, Contains, ContainsLengthBound, Currency, Imbalance, OnUnbalanced,
+		ExistenceRequirement, UnfilteredDispatchable, LockIdentifier,
+	},
+	dispatch::DispatchResult,
 	weights::{
 		Weight, IdentityFee,
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
@@ -96,12 +100,15 @@ pub const VERSION: RuntimeVersion = RuntimeVersion {
 	spec_name: create_runtime_str!("node-template"),
 	impl_name: create_runtime_str!("node-template"),
 	authoring_version: 1,
-	spec_version: 100,
+	spec_version: 101,
 	impl_version: 1,
 	apis: RUNTIME_API_VERSIONS,
 	transaction_version: 1,
 };
 
+/// pow(10,12) => Unit
+pub const UNIT: Balance = 1_000_000_000_000;
+
 pub const MILLISECS_PER_BLOCK: u64 = 6000;
 
 pub const SLOT_DURATION: u64 = MILLISECS_PER_BLOCK;
@@ -184,9 +191,10 @@ impl frame_system::Trait for Runtime {
 	/// This type is being generated by `construct_runtime!`.
 	type PalletInfo = PalletInfo;
 	/// What to do if a new account is created.
-	type OnNewAccount = ();
+	/// QuadraticFunding records the creation block for the account age voting policy.
+	type OnNewAccount = QuadraticFunding;
 	/// What to do if an account is fully reaped from the system.
-	type OnKilledAccount = ();
+	type OnKilledAccount = QuadraticFunding;
 	/// The data to be stored in an account.
 	type AccountData = pallet_balances::AccountData<Balance>;
 	/// Weight information for the extrinsics of this pallet.
@@ -249,9 +257,23 @@ parameter_types! {
 	pub const TransactionByteFee: Balance = 1;
 }
 
+type NegativeImbalance = <Balances as Currency<AccountId>>::NegativeImbalance;
+
+/// Split transaction fees between the designated QF round and the treasury, by the `fee_share` set by governance.
+/// There is no authorship pallet in this runtime, so validators do not get a share.
+pub struct DealWithFees;
+impl OnUnbalanced<NegativeImbalance> for DealWithFees {
+	fn on_nonzero_unbalanced(fees: NegativeImbalance) {
+		let share = QuadraticFunding::parameters().fee_share.deconstruct() as u32;
+		let (to_pool, to_treasury) = fees.ration(share, 100 - share);
+		QuadraticFunding::on_unbalanced(to_pool);
+		Treasury::on_unbalanced(to_treasury);
+	}
+}
+
 impl pallet_transaction_payment::Trait for Runtime {
 	type Currency = Balances;
-	type OnTransactionPayment = ();
+	type OnTransactionPayment = DealWithFees;
 	type TransactionByteFee = TransactionByteFee;
 	type WeightToFee = IdentityFee<Balance>;
 	type FeeMultiplierUpdate = ();
@@ -262,6 +284,171 @@ impl pallet_sudo::Trait for Runtime {
 	type Call = Call;
 }
 
+parameter_types! {
+	pub const CouncilMotionDuration: BlockNumber = 3 * DAYS;
+	pub const CouncilMaxProposals: u32 = 100;
+	pub const CouncilMaxMembers: u32 = 100;
+}
+
+/// The QF council, which administrates rounds and funds
+type CouncilCollective = pallet_collective::Instance1;
+impl pallet_collective::Trait<CouncilCollective> for Runtime {
+	type Origin = Origin;
+	type Proposal = Call;
+	type Event = Event;
+	type MotionDuration = CouncilMotionDuration;
+	type MaxProposals = CouncilMaxProposals;
+	type MaxMembers = CouncilMaxMembers;
+	type DefaultVote = pallet_collective::PrimeDefaultVote;
+	type WeightInfo = ();
+}
+
+/// Root, or more than half of the council. Root only works as long as `Sudo` is part of the runtime.
+type EnsureRootOrHalfCouncil = EnsureOneOf<
+	AccountId,
+	EnsureRoot<AccountId>,
+	pallet_collective::EnsureProportionMoreThan<_1, _2, AccountId, CouncilCollective>
+>;
+
+impl pallet_membership::Trait<pallet_membership::Instance1> for Runtime {
+	type Event = Event;
+	type AddOrigin = EnsureRootOrHalfCouncil;
+	type RemoveOrigin = EnsureRootOrHalfCouncil;
+	type SwapOrigin = EnsureRootOrHalfCouncil;
+	type ResetOrigin = EnsureRootOrHalfCouncil;
+	type PrimeOrigin = EnsureRootOrHalfCouncil;
+	type MembershipInitialized = Council;
+	type MembershipChanged = Council;
+}
+
+parameter_types! {
+	pub const ProposalBond: Permill = Permill::from_percent(5);
+	pub const ProposalBondMinimum: Balance = 1 * UNIT;
+	pub const SpendPeriod: BlockNumber = 1 * DAYS;
+	pub const Burn: Permill = Permill::from_percent(0);
+	pub const TipCountdown: BlockNumber = 1 * DAYS;
+	pub const TipFindersFee: Percent = Percent::from_percent(20);
+	pub const TipReportDepositBase: Balance = 1 * UNIT;
+	pub const DataDepositPerByte: Balance = UNIT / 100;
+	pub const BountyDepositBase: Balance = 1 * UNIT;
+	pub const BountyDepositPayoutDelay: BlockNumber = 1 * DAYS;
+	pub const BountyUpdatePeriod: BlockNumber = 14 * DAYS;
+	pub const MaximumReasonLength: u32 = 16384;
+	pub const BountyCuratorDeposit: Permill = Permill::from_percent(50);
+	pub const BountyValueMinimum: Balance = 5 * UNIT;
+	pub const TreasuryModuleId: ModuleId = ModuleId(*b"py/trsry");
+}
+
+/// The council members are the tippers of the treasury.
+pub struct CouncilTippers;
+impl Contains<AccountId> for CouncilTippers {
+	fn sorted_members() -> Vec<AccountId> {
+		CouncilMembership::members()
+	}
+}
+impl ContainsLengthBound for CouncilTippers {
+	fn max_len() -> usize {
+		CouncilMaxMembers::get() as usize
+	}
+	fn min_len() -> usize {
+		0
+	}
+}
+
+/// The account of the treasury, which funds the pools of QF rounds.
+pub struct TreasuryAccount;
+impl frame_support::traits::Get<AccountId> for TreasuryAccount {
+	fn get() -> AccountId {
+		Treasury::account_id()
+	}
+}
+
+impl pallet_treasury::Trait for Runtime {
+	type ModuleId = TreasuryModuleId;
+	type Currency = Balances;
+	type ApproveOrigin = EnsureRootOrHalfCouncil;
+	type RejectOrigin = EnsureRootOrHalfCouncil;
+	type Tippers = CouncilTippers;
+	type TipCountdown = TipCountdown;
+	type TipFindersFee = TipFindersFee;
+	type TipReportDepositBase = TipReportDepositBase;
+	type DataDepositPerByte = DataDepositPerByte;
+	type Event = Event;
+	type OnSlash = ();
+	type ProposalBond = ProposalBond;
+	type ProposalBondMinimum = ProposalBondMinimum;
+	type SpendPeriod = SpendPeriod;
+	type Burn = Burn;
+	type BountyDepositBase = BountyDepositBase;
+	type BountyDepositPayoutDelay = BountyDepositPayoutDelay;
+	type BountyUpdatePeriod = BountyUpdatePeriod;
+	type BountyCuratorDeposit = BountyCuratorDeposit;
+	type BountyValueMinimum = BountyValueMinimum;
+	type MaximumReasonLength = MaximumReasonLength;
+	type BurnDestination = ();
+	type WeightInfo = ();
+}
+
+parameter_types! {
+	pub const BasicDeposit: Balance = 10 * UNIT;
+	pub const FieldDeposit: Balance = 2 * UNIT;
+	pub const SubAccountDeposit: Balance = 2 * UNIT;
+	pub const MaxSubAccounts: u32 = 100;
+	pub const MaxAdditionalFields: u32 = 100;
+	pub const MaxRegistrars: u32 = 20;
+}
+
+impl pallet_identity::Trait for Runtime {
+	type Event = Event;
+	type Currency = Balances;
+	type BasicDeposit = BasicDeposit;
+	type FieldDeposit = FieldDeposit;
+	type SubAccountDeposit = SubAccountDeposit;
+	type MaxSubAccounts = MaxSubAccounts;
+	type MaxAdditionalFields = MaxAdditionalFields;
+	type MaxRegistrars = MaxRegistrars;
+	type Slashed = ();
+	type ForceOrigin = EnsureRootOrHalfCouncil;
+	type RegistrarOrigin = EnsureRootOrHalfCouncil;
+	type WeightInfo = ();
+}
+
+impl pallet_assets::Trait for Runtime {
+	type Event = Event;
+	type Balance = Balance;
+	type AssetId = u32;
+}
+
+/// The assets of QF rounds, the native currency from Balances and tokens from pallet-assets.
+pub struct QfAssets;
+impl pallet_quadratic_funding::MultiAsset<AccountId> for QfAssets {
+	type Balance = Balance;
+
+	fn free_balance(asset: pallet_quadratic_funding::AssetId, who: &AccountId) -> Balance {
+		match asset {
+			pallet_quadratic_funding::AssetId::Native => Balances::free_balance(who),
+			pallet_quadratic_funding::AssetId::Token(id) => Assets::balance(id, who.clone()),
+		}
+	}
+
+	fn transfer(asset: pallet_quadratic_funding::AssetId, from: &AccountId, to: &AccountId, amount: Balance) -> DispatchResult {
+		match asset {
+			pallet_quadratic_funding::AssetId::Native => {
+				<Balances as Currency<AccountId>>::transfer(from, to, amount, ExistenceRequirement::KeepAlive)
+			},
+			// pallet-assets rejects empty transfers
+			pallet_quadratic_funding::AssetId::Token(_) if amount == 0 => Ok(()),
+			pallet_quadratic_funding::AssetId::Token(id) => {
+				let target = <Runtime as frame_system::Trait>::Lookup::unlookup(to.clone());
+				pallet_assets::Call::<Runtime>::transfer(id, target, amount)
+					.dispatch_bypass_filter(Origin::signed(from.clone()))
+					.map(|_| ())
+					.map_err(|e| e.error)
+			},
+		}
+	}
+}
+
 parameter_types! {
 	// pow(10,12) => Unit, for easy fee control, we use pow(10,9)
     pub const VoteUnit: u128 = 1000000000;
@@ -273,6 +460,12 @@ parameter_types! {
 	pub const QuadraticFundingModuleId: ModuleId = ModuleId(*b"py/quafd");
 	pub const NameMinLength: usize = 3;
 	pub const NameMaxLength: usize = 32;
+	// Genesis values of the parameters, governance can change them by set_parameters
+	// Reserved from the account which opens a round
+	pub const RoundDeposit: Balance = 100 * UNIT;
+	pub const QfVoteLockId: LockIdentifier = *b"qf/votes";
+	// The share of transaction fees going to the pool of the designated round, the rest goes to the treasury
+	pub const QfFeeShare: Percent = Percent::from_percent(50);
 }
 
 /// Configure the template pallet in pallets/template.
@@ -288,6 +481,9 @@ impl pallet_quadratic_funding::Config for Runtime {
     // No action is taken when deposits are forfeited.
     type Slashed = ();
 
+	// The tax of each round goes to the treasury
+	type FeeDestination = Treasury;
+
     // Use the MinNickLength from the parameter_types block.
     type NumberOfUnitPerVote = NumberOfUnit;
 
@@ -303,8 +499,31 @@ impl pallet_quadratic_funding::Config for Runtime {
 	// The maximum length of project name
 	type NameMaxLength = NameMaxLength;
 
-	// Origin who can control the round
-	type AdminOrigin = EnsureRoot<AccountId>;
+	// Origin who can control the round, a majority of the council
+	type AdminOrigin = EnsureRootOrHalfCouncil;
+
+	// Only accounts judged by an identity registrar can vote
+	type VoterEligibility = pallet_quadratic_funding::IdentityJudgement<Runtime>;
+
+	// Votes can be weighted by the identity judgement of the voter
+	type IdentityLevels = pallet_quadratic_funding::IdentityJudgement<Runtime>;
+
+	// The deposit to open a round
+	type RoundDeposit = RoundDeposit;
+
+	// The council spends treasury funds on round pools
+	type TreasuryOrigin = EnsureRootOrHalfCouncil;
+
+	type TreasuryAccount = TreasuryAccount;
+
+	// Rounds can vote and match in the native currency or any asset of pallet-assets
+	type Assets = QfAssets;
+
+	// The lock of votes in lock mode rounds
+	type VoteLockId = QfVoteLockId;
+
+	// Genesis value of the share of transaction fees, governance can change it by set_parameters
+	type FeeShare = QfFeeShare;
 }
 
 // Create the runtime by composing the FRAME pallets that were previously configured.
@@ -322,13 +541,20 @@ construct_runtime!(
 		Balances: pallet_balances::{Module, Call, Storage, Config<T>, Event<T>},
 		TransactionPayment: pallet_transaction_payment::{Module, Storage},
 		Sudo: pallet_sudo::{Module, Call, Config<T>, Storage, Event<T>},
+		Identity: pallet_identity::{Module, Call, Storage, Event<T>},
+		Council: pallet_collective::<Instance1>::{Module, Call, Storage, Origin<T>, Event<T>, Config<T>},
+		CouncilMembership: pallet_membership::<Instance1>::{Module, Call, Storage, Event<T>, Config<T>},
+		Treasury: pallet_treasury::{Module, Call, Storage, Config, Event<T>},
+		Assets: pallet_assets::{Module, Call, Storage, Event<T>},
 		// Include the custom logic from the template pallet in the runtime.
-		QuadraticFunding: pallet_quadratic_funding::{Module, Call, Storage, Event<T>},
+		QuadraticFunding: pallet_quadratic_funding::{Module, Call, Storage, Config, Event<T>},
 	}
 );
 
 /// The address format for describing accounts.
 mod multiaddress;
+/// Storage migrations for runtime upgrades.
+pub mod migrations;
 pub type Address = multiaddress::MultiAddress<AccountId, ()>;
 /// Block header type as expected by this runtime.
 pub type Header = generic::Header<BlockNumber, BlakeTwo256>;
@@ -353,6 +579,7 @@ pub type UncheckedExtrinsic = generic::UncheckedExtrinsic<Address, Call, Signatu
 /// Extrinsic type that has already been checked.
 pub type CheckedExtrinsic = generic::CheckedExtrinsic<AccountId, Call, SignedExtra>;
 /// Executive: handles dispatch to the various modules.
+/// The runtime upgrade which removes `Sudo` should pass `migrations::RemoveSudo` as the last parameter.
 pub type Executive = frame_executive::Executive<
 	Runtime,
 	Block,
@@ -492,9 +719,33 @@ impl_runtime_apis! {
 		fn vote_cost(who: AccountId, round_id:u32, hash: Hash, ballot: u32) -> u32 {
 			QuadraticFunding::vote_cost(who, round_id, hash, ballot)
 		}
-		fn projects_per_round(round_id:u32) -> Vec<(Hash, u32, u32, u32)> {
+		fn projects_per_round(round_id:u32) -> Vec<pallet_quadratic_funding::RankingProject<Hash>> {
 			QuadraticFunding::projects_per_round(round_id)
 		}
+		fn parameters() -> pallet_quadratic_funding::ParametersInfo {
+			QuadraticFunding::parameters_info()
+		}
+		fn round_fees(round_id: u32) -> Vec<pallet_quadratic_funding::RoundFee> {
+			QuadraticFunding::round_fees(round_id)
+		}
+		fn project_matching(round_id: u32, hash: Hash) -> Vec<pallet_quadratic_funding::PoolMatching> {
+			QuadraticFunding::project_matching(round_id, hash)
+		}
+		fn contribution_impact(who: AccountId, round_id: u32, hash: Hash, amount: u32) -> (u32, u32) {
+			QuadraticFunding::contribution_impact(who, round_id, hash, amount)
+		}
+		fn batch_vote_cost(who: AccountId, round_id: u32, votes: Vec<(Hash, u32)>) -> u32 {
+			QuadraticFunding::batch_vote_cost(who, round_id, votes)
+		}
+		fn allocate_budget(who: AccountId, round_id: u32, budget: u32, weights: Vec<(Hash, u32)>) -> Vec<(Hash, u32)> {
+			QuadraticFunding::allocate_budget(who, round_id, budget, weights)
+		}
+		fn estimate_impact(who: AccountId, round_id: u32, hash: Hash, ballot: i32, proof: Option<Vec<Hash>>) -> pallet_quadratic_funding::VoteImpact<Hash> {
+			QuadraticFunding::estimate_impact(who, round_id, hash, ballot, proof)
+		}
+		fn vote_against_cost(who: AccountId, round_id: u32, hash: Hash, ballot: u32) -> u32 {
+			QuadraticFunding::vote_against_cost(who, round_id, hash, ballot)
+		}
 	}
 
 	#[cfg(feature = "runtime-benchmarks")]
@@ -0,0 +1,15 @@
+use frame_support::{traits::{Get, OnRuntimeUpgrade}, weights::{Weight, constants::RocksDbWeight}, storage::unhashed};
+use sp_core::hashing::twox_128;
+
+/// Clear the storage of pallet_sudo.
+///
+/// Once the council governs the chain, remove `Sudo` from `construct_runtime!`, bump `spec_version`
+/// and use this as the `OnRuntimeUpgrade` of `Executive`, so that the sudo key doesn't stay in storage.
+pub struct RemoveSudo;
+
+impl OnRuntimeUpgrade for RemoveSudo {
+	fn on_runtime_upgrade() -> Weight {
+		unhashed::kill_prefix(&twox_128(b"Sudo"));
+		RocksDbWeight::get().writes(1)
+	}
+}